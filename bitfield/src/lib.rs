@@ -56,63 +56,21 @@ impl Specifier for bool {
     }
 }
 
-seq! {N in 1..=64 {
+seq! { N in 1..=64 {
     pub enum B~N {}
-}}
-
-seq! { N in 1..=8 {
-    impl Specifier for B~N {
-        type Type = u8;
-        const BITS: usize = N;
-        const BYTES: usize = 1;
-
-        fn from_be_bytes_core(bytes: Vec<u8>) -> Self::Type {
-            Self::Type::from_be_bytes(bytes.try_into().unwrap())
-        }
-
-        fn to_be_bytes_core(value: Self::Type) -> Vec<u8> {
-            value.to_be_bytes().to_vec()
-        }
-    }
-}}
 
-seq! { N in 9..=16 {
     impl Specifier for B~N {
-        type Type = u16;
-        const BITS: usize = N;
-        const BYTES: usize = 2;
-
-        fn from_be_bytes_core(bytes: Vec<u8>) -> Self::Type {
-            Self::Type::from_be_bytes(bytes.try_into().unwrap())
+        #[seq_if(N <= 8)] {
+            type Type = u8;
+        } else #[seq_if(N <= 16)] {
+            type Type = u16;
+        } else #[seq_if(N <= 32)] {
+            type Type = u32;
+        } else {
+            type Type = u64;
         }
-
-        fn to_be_bytes_core(value: Self::Type) -> Vec<u8> {
-            value.to_be_bytes().to_vec()
-        }
-    }
-}}
-
-seq! { N in 17..=32 {
-    impl Specifier for B~N {
-        type Type = u32;
-        const BITS: usize = N;
-        const BYTES: usize = 4;
-
-        fn from_be_bytes_core(bytes: Vec<u8>) -> Self::Type {
-            Self::Type::from_be_bytes(bytes.try_into().unwrap())
-        }
-
-        fn to_be_bytes_core(value: Self::Type) -> Vec<u8> {
-            value.to_be_bytes().to_vec()
-        }
-    }
-}}
-
-seq! { N in 33..=64 {
-    impl Specifier for B~N {
-        type Type = u64;
         const BITS: usize = N;
-        const BYTES: usize = 8;
+        const BYTES: usize = ::core::mem::size_of::<Self::Type>();
 
         fn from_be_bytes_core(bytes: Vec<u8>) -> Self::Type {
             Self::Type::from_be_bytes(bytes.try_into().unwrap())
@@ -0,0 +1,291 @@
+use crate::{
+    add_custom_bounds, add_inferred_bounds, for_each_attr_option, get_crate_path,
+    get_custom_where_predicates, get_flag, get_format_string, get_generic_param_idents,
+    get_option_name, get_valid_types, parse_format_template, set_once, CompIdent, CompType, Errors,
+    FormatTemplate, FormatTrait, TargetFormat,
+};
+use proc_macro2::Span;
+use quote::quote;
+use std::collections::HashSet;
+use syn::__private::TokenStream2;
+use syn::ext::IdentExt;
+use syn::{
+    parse_quote, Attribute, Data, DeriveInput, Fields, Index, Member, NestedMeta, Path,
+    WherePredicate,
+};
+
+struct DisplayArm<'a> {
+    path: TokenStream2,
+    name: String,
+    fields: &'a Fields,
+    valid_types: Vec<Vec<CompType<'a>>>,
+    format: Option<TargetFormat>,
+    span: Span,
+}
+
+#[derive(Default)]
+struct DisplayOptions {
+    bounds: Option<Vec<WherePredicate>>,
+    format: Option<TargetFormat>,
+    krate: Option<Path>,
+}
+
+enum ArmBody {
+    Name(String),
+    Transparent(usize),
+    Template(FormatTemplate),
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let target_ident = &input.ident;
+    let target_generics_idents = get_generic_param_idents(&input.generics);
+
+    let mut errors = Errors::default();
+    let DisplayOptions {
+        bounds,
+        format,
+        krate,
+    } = get_display_options(&input.attrs, &mut errors);
+    let krate = krate.unwrap_or_else(|| parse_quote!(::core));
+
+    let mut arms = vec![];
+    match &input.data {
+        Data::Struct(data) => arms.push(DisplayArm {
+            path: quote!(Self),
+            name: target_ident.unraw().to_string(),
+            fields: &data.fields,
+            valid_types: get_fields_valid_types(&data.fields, &target_generics_idents),
+            format,
+            span: target_ident.span(),
+        }),
+        Data::Enum(data) => {
+            if let Some(TargetFormat::Transparent(span)) = format {
+                errors.push(syn::Error::new(
+                    span,
+                    "put `transparent` on the variants of an enum",
+                ));
+            } else if let Some(TargetFormat::Template(lit)) = format {
+                errors.push(syn::Error::new_spanned(
+                    lit,
+                    "put format strings on the variants of an enum",
+                ));
+            }
+
+            for variant in &data.variants {
+                let options = get_display_options(&variant.attrs, &mut errors);
+                if options.bounds.is_some() {
+                    errors.push(syn::Error::new_spanned(
+                        &variant.ident,
+                        "`bound` is only allowed on the enum itself",
+                    ));
+                }
+                if let Some(path) = options.krate {
+                    errors.push(syn::Error::new_spanned(
+                        path,
+                        "`crate` is only allowed on the enum itself",
+                    ));
+                }
+
+                let ident = &variant.ident;
+                arms.push(DisplayArm {
+                    path: quote!(Self::#ident),
+                    name: ident.unraw().to_string(),
+                    fields: &variant.fields,
+                    valid_types: get_fields_valid_types(&variant.fields, &target_generics_idents),
+                    format: options.format,
+                    span: ident.span(),
+                });
+            }
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "CustomDisplay does not support unions",
+            ))
+        }
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => data.fields.iter().collect(),
+        Data::Enum(data) => data.variants.iter().flat_map(|v| &v.fields).collect(),
+        Data::Union(_) => vec![],
+    };
+    for field in fields {
+        for_each_attr_option(&field.attrs, "display", &mut errors, |nm| {
+            Err(syn::Error::new_spanned(
+                nm,
+                "`display` options are not supported on fields",
+            ))
+        });
+    }
+
+    let bodies: Vec<_> = arms
+        .iter()
+        .filter_map(|arm| match get_arm_body(arm) {
+            Ok(body) => Some(body),
+            Err(e) => {
+                errors.push(e);
+                None
+            }
+        })
+        .collect();
+    errors.finish(())?;
+
+    let generics = match &bounds {
+        Some(bounds) => add_custom_bounds(input.generics.clone(), bounds),
+        None => {
+            let field_uses = arms.iter().zip(&bodies).flat_map(|(arm, body)| {
+                let uses: Vec<_> = match body {
+                    ArmBody::Name(_) => vec![],
+                    ArmBody::Transparent(i) => vec![(FormatTrait::Display, *i)],
+                    ArmBody::Template(FormatTemplate { args, .. }) => args
+                        .iter()
+                        .flat_map(|arg| arg.traits.iter().map(|t| (*t, arg.index)))
+                        .collect(),
+                };
+                uses.into_iter()
+                    .map(|(format_trait, i)| (format_trait, arm.valid_types[i].as_slice()))
+            });
+            add_inferred_bounds(
+                input.generics.clone(),
+                &target_generics_idents,
+                field_uses,
+                &krate,
+            )
+        }
+    };
+
+    let scrutinee = match arms.as_slice() {
+        [] => quote!(*self),
+        _ => quote!(self),
+    };
+    let arms = arms
+        .iter()
+        .zip(&bodies)
+        .map(|(arm, body)| render_arm(arm, body, &krate));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::fmt::Display for #target_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut #krate::fmt::Formatter<'_>) -> #krate::result::Result<(), #krate::fmt::Error> {
+                match #scrutinee {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+fn get_display_options(attrs: &[Attribute], errors: &mut Errors) -> DisplayOptions {
+    let mut options = DisplayOptions::default();
+    let DisplayOptions {
+        bounds,
+        format,
+        krate,
+    } = &mut options;
+
+    for_each_attr_option(attrs, "display", errors, |nm| {
+        let meta = match nm {
+            NestedMeta::Lit(_) => {
+                let template = TargetFormat::Template(get_format_string(nm)?.clone());
+                return set_once(format, template, nm, "the display format");
+            }
+            NestedMeta::Meta(meta) => meta,
+        };
+
+        match get_option_name(meta)?.as_str() {
+            "transparent" => {
+                let transparent = TargetFormat::Transparent(get_flag(meta, "transparent")?);
+                set_once(format, transparent, nm, "the display format")
+            }
+            "fmt" => {
+                let template = TargetFormat::Template(get_format_string(nm)?.clone());
+                set_once(format, template, nm, "the display format")
+            }
+            "bound" => {
+                bounds
+                    .get_or_insert_with(Vec::new)
+                    .extend(get_custom_where_predicates(meta)?);
+                Ok(())
+            }
+            "crate" => set_once(krate, get_crate_path(meta)?, nm, "`crate`"),
+            name => Err(syn::Error::new_spanned(
+                meta.path(),
+                format!("unknown `display` option `{}`", name),
+            )),
+        }
+    });
+
+    options
+}
+
+fn get_fields_valid_types<'a>(
+    fields: &'a Fields,
+    target_generics_idents: &HashSet<CompIdent>,
+) -> Vec<Vec<CompType<'a>>> {
+    fields
+        .iter()
+        .map(|f| get_valid_types(&f.ty, target_generics_idents))
+        .collect()
+}
+
+fn get_arm_body(arm: &DisplayArm) -> syn::Result<ArmBody> {
+    match &arm.format {
+        Some(TargetFormat::Transparent(span)) => match arm.fields.len() {
+            1 => Ok(ArmBody::Transparent(0)),
+            _ => Err(syn::Error::new(
+                *span,
+                "`transparent` requires exactly one field",
+            )),
+        },
+        Some(TargetFormat::Template(lit)) => {
+            parse_format_template(lit, arm.fields).map(ArmBody::Template)
+        }
+        None if arm.fields.is_empty() => Ok(ArmBody::Name(arm.name.clone())),
+        None => Err(syn::Error::new(
+            arm.span,
+            "missing `#[display(\"...\")]` or `#[display(transparent)]`",
+        )),
+    }
+}
+
+fn member(fields: &Fields, index: usize) -> Member {
+    match fields.iter().nth(index).and_then(|f| f.ident.clone()) {
+        Some(ident) => Member::Named(ident),
+        None => Member::Unnamed(Index::from(index)),
+    }
+}
+
+fn render_arm(arm: &DisplayArm, body: &ArmBody, krate: &Path) -> TokenStream2 {
+    let path = &arm.path;
+
+    match body {
+        ArmBody::Name(name) => quote! {
+            #path { .. } => f.write_str(#name),
+        },
+        ArmBody::Transparent(index) => {
+            let member = member(arm.fields, *index);
+            quote! {
+                #path { #member: __field, .. } => #krate::fmt::Display::fmt(__field, f),
+            }
+        }
+        ArmBody::Template(FormatTemplate { format, args }) => {
+            let patterns = args.iter().map(|arg| {
+                let member = member(arm.fields, arg.index);
+                let name = arg.name();
+                quote!(#member: #name)
+            });
+            let args = args.iter().map(|arg| {
+                let name = arg.name();
+                if arg.by_value {
+                    quote!(#name = *#name)
+                } else {
+                    quote!(#name = #name)
+                }
+            });
+            quote! {
+                #path { #(#patterns,)* .. } => f.write_fmt(format_args!(#format, #(#args),*)),
+            }
+        }
+    }
+}
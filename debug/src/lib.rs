@@ -1,112 +1,860 @@
 use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
-use quote::quote;
+use proc_macro2::{Ident, Span, TokenTree};
+use quote::{quote, quote_spanned};
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use syn::__private::TokenStream2;
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::{
     parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Field, Fields, GenericArgument,
-    GenericParam, Generics, Lit, Meta, NestedMeta, Path, PathArguments, Type, WherePredicate,
+    GenericParam, Generics, Index, Lit, LitStr, Member, Meta, MetaNameValue, NestedMeta, Path,
+    PathArguments, Token, Type, TypeArray, TypeGroup, TypeParen, TypePath, TypeReference,
+    TypeSlice, TypeTuple, WherePredicate,
 };
 
+mod display;
+
 #[proc_macro_derive(CustomDebug, attributes(debug))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let target_ident = input.ident;
     let Data::Struct(data) = input.data else {
-        return syn::Error::new(Span::call_site(), "Unsupported".to_string()).into_compile_error().into();
+        return syn::Error::new(Span::call_site(), "Unsupported".to_string())
+            .into_compile_error()
+            .into();
     };
 
-    let (field_debug_data, valid_types): (Vec<_>, Vec<_>) =
-        analyze_fields(&data.fields).into_iter().unzip();
+    let target_generics_idents = get_generic_param_idents(&input.generics);
 
-    let target_custom_where_predicates = match get_custom_where_predicates(&input.attrs) {
-        Ok(x) => x,
-        Err(e) => return e.into_compile_error().into(),
+    let (target_debug_info, field_debug_data) = match (
+        get_target_debug_info(&input.attrs),
+        analyze_fields(&data.fields, &target_generics_idents),
+    ) {
+        (Ok(target_debug_info), Ok(field_debug_data)) => (target_debug_info, field_debug_data),
+        (Err(mut e), Err(field_errors)) => {
+            e.combine(field_errors);
+            return e.into_compile_error().into();
+        }
+        (Err(e), _) | (_, Err(e)) => return e.into_compile_error().into(),
     };
 
-    let generics = if target_custom_where_predicates.is_empty() {
-        let target_generics_idents = get_generic_param_idents(&input.generics);
-
-        let mut used_type_params = HashSet::<CompPath>::new();
-        for valid_types in valid_types {
-            used_type_params.extend(valid_types.into_iter());
+    let body = match &target_debug_info.format {
+        None => {
+            let flatten = field_debug_data.iter().find_map(|f| match f.format {
+                FieldFormat::Flatten(span) if !matches!(data.fields, Fields::Named(_)) => {
+                    Some(span)
+                }
+                _ => None,
+            });
+            if let Some(span) = flatten {
+                return syn::Error::new(span, "`flatten` requires a struct with named fields")
+                    .into_compile_error()
+                    .into();
+            }
+            FmtBody::Fields
         }
+        Some(TargetFormat::Transparent(span)) => {
+            let mut fields = field_debug_data.iter().enumerate().filter(|(_, f)| !f.skip);
+            match (fields.next(), fields.next()) {
+                (Some((i, field)), None) => match field.format {
+                    FieldFormat::Flatten(span) => {
+                        return syn::Error::new(
+                            span,
+                            "`flatten` cannot be used in a transparent struct",
+                        )
+                        .into_compile_error()
+                        .into()
+                    }
+                    _ => FmtBody::Transparent(i),
+                },
+                _ => return syn::Error::new(
+                    *span,
+                    "`transparent` requires exactly one field that is not skipped or PhantomData",
+                )
+                .into_compile_error()
+                .into(),
+            }
+        }
+        Some(TargetFormat::Template(lit)) => match parse_format_template(lit, &data.fields) {
+            Ok(x) => FmtBody::Template(x),
+            Err(e) => return e.into_compile_error().into(),
+        },
+    };
 
-        add_trait_bounds(input.generics, &target_generics_idents, &used_type_params)
+    let generics = if let Some(bounds) = &target_debug_info.bounds {
+        add_custom_bounds(input.generics.clone(), bounds)
     } else {
-        add_custom_bounds(input.generics, &target_custom_where_predicates)
+        let field_uses: Vec<_> = match &body {
+            FmtBody::Fields => field_debug_data
+                .iter()
+                .enumerate()
+                .filter(|(_, field)| !field.skip)
+                .map(|(i, field)| (field.format.format_trait(), i))
+                .collect(),
+            FmtBody::Transparent(i) => vec![(field_debug_data[*i].format.format_trait(), *i)],
+            FmtBody::Template(FormatTemplate { args, .. }) => args
+                .iter()
+                .flat_map(|arg| arg.traits.iter().map(|t| (*t, arg.index)))
+                .collect(),
+        };
+
+        let mut custom_bounded_fields = vec![];
+        let mut inferred_field_uses = vec![];
+        for (format_trait, i) in field_uses {
+            let field = &field_debug_data[i];
+            if field.bounds.is_none() {
+                inferred_field_uses.push((format_trait, field.valid_types.as_slice()));
+            } else if !custom_bounded_fields.contains(&i) {
+                custom_bounded_fields.push(i);
+            }
+        }
+
+        let generics = add_inferred_bounds(
+            input.generics.clone(),
+            &target_generics_idents,
+            inferred_field_uses,
+            &target_debug_info.krate,
+        );
+
+        custom_bounded_fields
+            .into_iter()
+            .fold(generics, |generics, i| {
+                add_custom_bounds(generics, field_debug_data[i].bounds.as_ref().unwrap())
+            })
     };
 
-    render(&target_ident, &generics, &field_debug_data).into()
+    let reflection = match target_debug_info.reflect {
+        Some(span) => {
+            if field_debug_data
+                .iter()
+                .any(|f| matches!(f.format, FieldFormat::Flatten(_)))
+            {
+                return syn::Error::new(span, "`reflect` cannot be used with flattened fields")
+                    .into_compile_error()
+                    .into();
+            }
+            // `debug_fields` yields the fields themselves rather than their
+            // formatted values, so it also needs them to be Debug, unless the
+            // bounds are given by hand.
+            let predicates: Vec<WherePredicate> = match &target_debug_info.bounds {
+                Some(_) => vec![],
+                None => field_debug_data
+                    .iter()
+                    .filter(|f| {
+                        !f.skip
+                            && f.bounds.is_none()
+                            && mentions_generics(f.ty, &target_generics_idents)
+                    })
+                    .map(|f| {
+                        let ty = f.ty;
+                        let bound = FormatTrait::Debug.path(&target_debug_info.krate);
+                        parse_quote!(#ty: #bound)
+                    })
+                    .collect(),
+            };
+            let fields_generics = add_custom_bounds(generics.clone(), &predicates);
+            Some(render_reflection(
+                &target_ident,
+                &input.generics,
+                &fields_generics,
+                &target_debug_info,
+                &field_debug_data,
+            ))
+        }
+        None => None,
+    };
+
+    let debug_impl = render(
+        &target_ident,
+        &generics,
+        &data.fields,
+        &target_debug_info,
+        &field_debug_data,
+        &body,
+    );
+
+    quote! {
+        #debug_impl
+        #reflection
+    }
+    .into()
 }
 
-struct FieldDebugInfo {
-    ident: Ident,
-    debug_format: Option<String>,
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    display::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-fn get_custom_where_predicates(attrs: &Vec<Attribute>) -> syn::Result<Vec<WherePredicate>> {
-    attrs
-        .iter()
-        .map(|a| -> syn::Result<_> {
-            match a.parse_meta()? {
-                Meta::List(l) => match l.path.get_ident() {
-                    Some(ident) if ident == "debug" => {
-                        let nm = l.nested.first().ok_or(syn::Error::new_spanned(&l, "?"))?;
-                        let meta = wrap_match!(nm => NestedMeta::Meta)
-                            .ok_or(syn::Error::new_spanned(nm, "??"))?;
-                        let nv = wrap_match!(meta => Meta::NameValue)
-                            .ok_or(syn::Error::new_spanned(meta, "???"))?;
-                        let ident = nv
-                            .path
-                            .get_ident()
-                            .ok_or(syn::Error::new_spanned(&nv.path, "????"))?;
-                        if ident.to_string() != "bound" {
-                            return Err(syn::Error::new_spanned(nv, "should be `bound = (...)`"));
-                        }
-                        if let Lit::Str(str) = &nv.lit {
-                            let str = str.token().to_string();
-                            let str = str.trim_matches(|c| !char::is_alphanumeric(c));
-                            let w = syn::parse_str::<WherePredicate>(&str)?;
-                            Ok(w)
-                        } else {
-                            Err(syn::Error::new_spanned(&nv.lit, "invalid format"))
-                        }
+struct FieldDebugInfo<'a> {
+    member: Member,
+    ty: &'a Type,
+    rename: Option<LitStr>,
+    format: FieldFormat,
+    valid_types: Vec<CompType<'a>>,
+    skip: bool,
+    bounds: Option<Vec<WherePredicate>>,
+}
+
+impl<'a> FieldDebugInfo<'a> {
+    fn name(&self, rename_all: Option<RenameRule>) -> Option<String> {
+        match (&self.member, &self.rename) {
+            (_, Some(rename)) => Some(rename.value()),
+            (Member::Named(ident), None) => {
+                let name = ident.unraw().to_string();
+                Some(match rename_all {
+                    Some(rule) => rule.apply(&name),
+                    None => name,
+                })
+            }
+            (Member::Unnamed(_), None) => None,
+        }
+    }
+}
+
+struct TargetDebugInfo {
+    bounds: Option<Vec<WherePredicate>>,
+    format: Option<TargetFormat>,
+    rename: Option<LitStr>,
+    rename_all: Option<RenameRule>,
+    reflect: Option<Span>,
+    krate: Path,
+}
+
+impl TargetDebugInfo {
+    fn name(&self, target_ident: &Ident) -> String {
+        match &self.rename {
+            Some(rename) => rename.value(),
+            None => target_ident.unraw().to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn from_lit(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lower),
+            "UPPERCASE" => Ok(RenameRule::Upper),
+            "PascalCase" => Ok(RenameRule::Pascal),
+            "camelCase" => Ok(RenameRule::Camel),
+            "snake_case" => Ok(RenameRule::Snake),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnake),
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "SCREAMING-KEBAB-CASE" => Ok(RenameRule::ScreamingKebab),
+            _ => Err(syn::Error::new_spanned(
+                lit,
+                "unknown case, expected one of \"lowercase\", \"UPPERCASE\", \"PascalCase\", \
+                 \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \"kebab-case\" \
+                 or \"SCREAMING-KEBAB-CASE\"",
+            )),
+        }
+    }
+
+    fn apply(self, field_name: &str) -> String {
+        let words = field_name.split('_').filter(|w| !w.is_empty());
+        let capitalize = |w: &str| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        };
+
+        match self {
+            RenameRule::Lower | RenameRule::Snake => field_name.to_string(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => field_name.to_ascii_uppercase(),
+            RenameRule::Pascal => words.map(capitalize).collect(),
+            RenameRule::Camel => {
+                let pascal: String = words.map(capitalize).collect();
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(c) => c.to_lowercase().chain(chars).collect(),
+                    None => pascal,
+                }
+            }
+            RenameRule::Kebab => field_name.replace('_', "-"),
+            RenameRule::ScreamingKebab => field_name.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
+
+enum TargetFormat {
+    Transparent(Span),
+    Template(LitStr),
+}
+
+enum FmtBody {
+    Fields,
+    /// Forwards to the field at this index, in its own format.
+    Transparent(usize),
+    Template(FormatTemplate),
+}
+
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, e: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(e),
+            None => self.0 = Some(e),
+        }
+    }
+
+    fn finish<T>(self, value: T) -> syn::Result<T> {
+        match self.0 {
+            Some(e) => Err(e),
+            None => Ok(value),
+        }
+    }
+}
+
+fn for_each_attr_option(
+    attrs: &[Attribute],
+    attr_name: &str,
+    errors: &mut Errors,
+    mut f: impl FnMut(&NestedMeta) -> syn::Result<()>,
+) {
+    for attr in attrs.iter().filter(|a| a.path.is_ident(attr_name)) {
+        let result = match attr.parse_meta() {
+            Ok(Meta::List(l)) if l.nested.is_empty() => Err(syn::Error::new_spanned(
+                &l,
+                format!("expected at least one option in `#[{}(...)]`", attr_name),
+            )),
+            Ok(Meta::List(l)) => {
+                for nm in &l.nested {
+                    if let Err(e) = f(nm) {
+                        errors.push(e);
                     }
-                    _ => unimplemented!(),
-                },
-                _ => unimplemented!(),
+                }
+                Ok(())
             }
-        })
-        .collect::<Result<_, _>>()
+            Ok(Meta::NameValue(nv)) => f(&NestedMeta::Lit(nv.lit)),
+            Ok(meta @ Meta::Path(_)) => Err(syn::Error::new_spanned(
+                meta,
+                format!("expected `#[{0}(...)]` or `#[{0} = \"...\"]`", attr_name),
+            )),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            errors.push(e);
+        }
+    }
+}
+
+fn get_option_name(meta: &Meta) -> syn::Result<String> {
+    match meta.path().get_ident() {
+        Some(ident) => Ok(ident.to_string()),
+        None => Err(syn::Error::new_spanned(
+            meta.path(),
+            "expected the name of an option",
+        )),
+    }
+}
+
+fn get_lit_str<'a>(meta: &'a Meta, name: &str) -> syn::Result<&'a LitStr> {
+    match meta {
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::Str(str), ..
+        }) => Ok(str),
+        Meta::NameValue(nv) => Err(syn::Error::new_spanned(
+            &nv.lit,
+            format!("expected a string literal for `{}`", name),
+        )),
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{} = \"...\"`", name),
+        )),
+    }
+}
+
+fn get_lit_usize(meta: &Meta, name: &str) -> syn::Result<(usize, Span)> {
+    match meta {
+        Meta::NameValue(MetaNameValue {
+            lit: Lit::Int(int), ..
+        }) => Ok((int.base10_parse()?, int.span())),
+        Meta::NameValue(nv) => Err(syn::Error::new_spanned(
+            &nv.lit,
+            format!("expected an integer for `{}`", name),
+        )),
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{} = ...`", name),
+        )),
+    }
+}
+
+fn get_flag(meta: &Meta, name: &str) -> syn::Result<Span> {
+    match meta {
+        Meta::Path(path) => Ok(path.get_ident().unwrap().span()),
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("`{}` does not take a value", name),
+        )),
+    }
 }
 
-fn analyze_fields(fields: &Fields) -> Vec<(Option<FieldDebugInfo>, Vec<CompPath>)> {
-    fields
+fn get_format_string(nm: &NestedMeta) -> syn::Result<&LitStr> {
+    match nm {
+        NestedMeta::Lit(Lit::Str(str)) => Ok(str),
+        NestedMeta::Lit(lit) => Err(syn::Error::new_spanned(lit, "expected a format string")),
+        NestedMeta::Meta(meta) => get_lit_str(meta, "fmt"),
+    }
+}
+
+fn get_custom_where_predicates(meta: &Meta) -> syn::Result<Vec<WherePredicate>> {
+    let predicates = get_lit_str(meta, "bound")?
+        .parse_with(Punctuated::<WherePredicate, Token![,]>::parse_terminated)?;
+    Ok(predicates.into_iter().collect())
+}
+
+fn get_crate_path(meta: &Meta) -> syn::Result<Path> {
+    get_lit_str(meta, "crate")?.parse_with(Path::parse_mod_style)
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T, nm: &NestedMeta, what: &str) -> syn::Result<()> {
+    match slot.replace(value) {
+        Some(_) => Err(syn::Error::new_spanned(
+            nm,
+            format!("{} is given more than once", what),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn get_target_debug_info(attrs: &[Attribute]) -> syn::Result<TargetDebugInfo> {
+    let mut errors = Errors::default();
+    let mut bounds: Option<Vec<WherePredicate>> = None;
+    let mut format = None;
+    let mut rename = None;
+    let mut rename_all = None;
+    let mut reflect = None;
+    let mut krate = None;
+
+    for_each_attr_option(attrs, "debug", &mut errors, |nm| {
+        let meta = match nm {
+            NestedMeta::Lit(_) => {
+                let template = TargetFormat::Template(get_format_string(nm)?.clone());
+                return set_once(&mut format, template, nm, "the struct format");
+            }
+            NestedMeta::Meta(meta) => meta,
+        };
+
+        match get_option_name(meta)?.as_str() {
+            "transparent" => {
+                let transparent = TargetFormat::Transparent(get_flag(meta, "transparent")?);
+                set_once(&mut format, transparent, nm, "the struct format")
+            }
+            "fmt" => {
+                let template = TargetFormat::Template(get_format_string(nm)?.clone());
+                set_once(&mut format, template, nm, "the struct format")
+            }
+            "bound" => {
+                bounds
+                    .get_or_insert_with(Vec::new)
+                    .extend(get_custom_where_predicates(meta)?);
+                Ok(())
+            }
+            "rename" => set_once(
+                &mut rename,
+                get_lit_str(meta, "rename")?.clone(),
+                nm,
+                "`rename`",
+            ),
+            "rename_all" => {
+                let rule = RenameRule::from_lit(get_lit_str(meta, "rename_all")?)?;
+                set_once(&mut rename_all, rule, nm, "`rename_all`")
+            }
+            "reflect" => set_once(&mut reflect, get_flag(meta, "reflect")?, nm, "`reflect`"),
+            "crate" => set_once(&mut krate, get_crate_path(meta)?, nm, "`crate`"),
+            name => Err(syn::Error::new_spanned(
+                meta.path(),
+                format!("unknown `debug` option `{}` for a struct", name),
+            )),
+        }
+    });
+
+    errors.finish(TargetDebugInfo {
+        bounds,
+        format,
+        rename,
+        rename_all,
+        reflect,
+        krate: krate.unwrap_or_else(|| parse_quote!(::core)),
+    })
+}
+
+fn analyze_fields<'a>(
+    fields: &'a Fields,
+    target_generics_idents: &HashSet<CompIdent>,
+) -> syn::Result<Vec<FieldDebugInfo<'a>>> {
+    let mut errors = Errors::default();
+
+    let field_debug_data = fields
         .iter()
+        .enumerate()
         .map(
-            |Field {
-                 ident, attrs, ty, ..
-             }| {
-                let valid_types = get_valid_types(ty);
-                if valid_types.is_empty() {
-                    (None, valid_types)
-                } else {
-                    let debug_format = get_debug_format(attrs);
-
-                    (
-                        Some(FieldDebugInfo {
-                            ident: ident.clone().unwrap(),
-                            debug_format,
-                        }),
-                        valid_types,
-                    )
+            |(
+                i,
+                Field {
+                    ident, attrs, ty, ..
+                },
+            )| {
+                let member = match ident {
+                    Some(ident) => Member::Named(ident.clone()),
+                    None => Member::Unnamed(Index::from(i)),
+                };
+
+                let mut debug_format = None;
+                let mut rename = None;
+                let mut bounds: Option<Vec<WherePredicate>> = None;
+                let mut skip = None;
+                let mut options = FieldOptions::default();
+
+                for_each_attr_option(attrs, "debug", &mut errors, |nm| {
+                    let meta = match nm {
+                        NestedMeta::Lit(_) => {
+                            let format = get_format_string(nm)?.clone();
+                            return set_once(&mut debug_format, format, nm, "the field format");
+                        }
+                        NestedMeta::Meta(meta) => meta,
+                    };
+
+                    match get_option_name(meta)?.as_str() {
+                        "fmt" => {
+                            let format = get_format_string(nm)?.clone();
+                            set_once(&mut debug_format, format, nm, "the field format")
+                        }
+                        "bound" => {
+                            bounds
+                                .get_or_insert_with(Vec::new)
+                                .extend(get_custom_where_predicates(meta)?);
+                            Ok(())
+                        }
+                        "rename" if ident.is_none() => Err(syn::Error::new_spanned(
+                            meta,
+                            "`rename` only applies to named fields",
+                        )),
+                        "rename" => {
+                            let name = get_lit_str(meta, "rename")?.clone();
+                            set_once(&mut rename, name, nm, "`rename`")
+                        }
+                        "alt" => {
+                            let alt = get_lit_str(meta, "alt")?.clone();
+                            set_once(&mut options.alt, alt, nm, "the alternate field format")
+                        }
+                        "skip" => set_once(&mut skip, get_flag(meta, "skip")?, nm, "`skip`"),
+                        "hex" => set_once(&mut options.hex, get_flag(meta, "hex")?, nm, "`hex`"),
+                        "truncate" => {
+                            let truncate = get_lit_usize(meta, "truncate")?;
+                            set_once(&mut options.truncate, truncate, nm, "`truncate`")
+                        }
+                        "as_list" => {
+                            let view = (FieldView::List, get_flag(meta, "as_list")?);
+                            set_once(&mut options.view, view, nm, "the field view")
+                        }
+                        "flatten" => {
+                            let flatten = get_flag(meta, "flatten")?;
+                            set_once(&mut options.flatten, flatten, nm, "`flatten`")
+                        }
+                        "as_map" => {
+                            let view = (FieldView::Map, get_flag(meta, "as_map")?);
+                            set_once(&mut options.view, view, nm, "the field view")
+                        }
+                        name => Err(syn::Error::new_spanned(
+                            meta.path(),
+                            format!("unknown `debug` option `{}` for a field", name),
+                        )),
+                    }
+                });
+
+                let format = get_field_format(ty, target_generics_idents, debug_format, options)
+                    .unwrap_or_else(|e| {
+                        errors.push(e);
+                        FieldFormat::Debug
+                    });
+
+                FieldDebugInfo {
+                    member,
+                    ty,
+                    rename,
+                    format,
+                    valid_types: get_valid_types(ty, target_generics_idents),
+                    skip: skip.is_some() || is_phantom_data(ty),
+                    bounds,
                 }
             },
         )
-        .collect()
+        .collect();
+
+    errors.finish(field_debug_data)
+}
+
+enum FieldFormat {
+    Debug,
+    Custom { fmt: LitStr, alt: Option<LitStr> },
+    Hex,
+    Str { truncate: usize },
+    List { hex: bool, truncate: Option<usize> },
+    Map { truncate: Option<usize> },
+    Flatten(Span),
+}
+
+impl FieldFormat {
+    fn format_trait(&self) -> FormatTrait {
+        match self {
+            FieldFormat::Hex | FieldFormat::List { hex: true, .. } => FormatTrait::LowerHex,
+            _ => FormatTrait::Debug,
+        }
+    }
+
+    fn uses_debug_with(&self) -> bool {
+        matches!(
+            self,
+            FieldFormat::Custom { alt: Some(_), .. }
+                | FieldFormat::Str { .. }
+                | FieldFormat::List { .. }
+                | FieldFormat::Map { .. }
+        )
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FieldView {
+    List,
+    Map,
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    alt: Option<LitStr>,
+    hex: Option<Span>,
+    truncate: Option<(usize, Span)>,
+    view: Option<(FieldView, Span)>,
+    flatten: Option<Span>,
+}
+
+fn get_field_format(
+    ty: &Type,
+    target_generics_idents: &HashSet<CompIdent>,
+    debug_format: Option<LitStr>,
+    FieldOptions {
+        alt,
+        hex,
+        truncate,
+        view,
+        flatten,
+    }: FieldOptions,
+) -> syn::Result<FieldFormat> {
+    let truncate_span = truncate.map(|(_, span)| span);
+    let truncate = truncate.map(|(n, _)| n);
+
+    if let Some(flatten) = flatten {
+        let other = debug_format
+            .as_ref()
+            .or(alt.as_ref())
+            .map(LitStr::span)
+            .or(hex)
+            .or(truncate_span)
+            .or(view.map(|(_, span)| span));
+        return match other {
+            Some(span) => Err(syn::Error::new(
+                span,
+                "`flatten` cannot be combined with other formatting options",
+            )),
+            None => Ok(FieldFormat::Flatten(flatten)),
+        };
+    }
+
+    if let Some(debug_format) = debug_format {
+        return match hex.or(truncate_span).or(view.map(|(_, span)| span)) {
+            Some(span) => Err(syn::Error::new(
+                span,
+                "a format string cannot be combined with other formatting options",
+            )),
+            None => Ok(FieldFormat::Custom {
+                alt: alt.or_else(|| get_pretty_format(&debug_format)),
+                fmt: debug_format,
+            }),
+        };
+    }
+
+    if let Some(alt) = alt {
+        return Err(syn::Error::new_spanned(
+            alt,
+            "`alt` requires a format string for the non-alternate mode",
+        ));
+    }
+
+    match view {
+        Some((FieldView::Map, _)) => match hex {
+            Some(span) => Err(syn::Error::new(
+                span,
+                "`hex` cannot be combined with `as_map`",
+            )),
+            None => Ok(FieldFormat::Map { truncate }),
+        },
+        Some((FieldView::List, _)) => Ok(FieldFormat::List {
+            hex: hex.is_some(),
+            truncate,
+        }),
+        None => match (hex, truncate) {
+            (None, None) => Ok(FieldFormat::Debug),
+            (Some(span), Some(_)) if is_string_type(ty) => Err(syn::Error::new(
+                span,
+                "`hex` cannot be used on a string field",
+            )),
+            (None, Some(truncate)) if is_string_type(ty) => Ok(FieldFormat::Str { truncate }),
+            (Some(_), None) if is_integer_type(ty, target_generics_idents) => Ok(FieldFormat::Hex),
+            (Some(_), Some(_)) if is_integer_type(ty, target_generics_idents) => {
+                Err(syn::Error::new(
+                    truncate_span.unwrap(),
+                    "`truncate` cannot be used on an integer field",
+                ))
+            }
+            // A type parameter may be a collection, so only an integer type
+            // written out is known not to be one.
+            (None, Some(_)) if is_primitive_integer(ty) => Err(syn::Error::new(
+                truncate_span.unwrap(),
+                "`truncate` cannot be used on an integer field",
+            )),
+            (hex, truncate) => Ok(FieldFormat::List {
+                hex: hex.is_some(),
+                truncate,
+            }),
+        },
+    }
+}
+
+// Derives the `{:#?}` counterpart of a field format by adding the `#` flag to
+// its Debug placeholders, so that nested values are pretty-printed as well.
+// Returns None if nothing changes or if the string is malformed, in which case
+// `format_args!` reports the error.
+fn get_pretty_format(lit: &LitStr) -> Option<LitStr> {
+    let source = lit.value();
+    let mut format = String::new();
+    let mut changed = false;
+
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        format.push(c);
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => format.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => format.push('}'),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        c => placeholder.push(c),
+                    }
+                }
+                if let Some((name, spec)) = placeholder.split_once(':') {
+                    if FormatTrait::from_spec(spec) == FormatTrait::Debug {
+                        let spec: Vec<_> = spec.chars().collect();
+                        let mut flags = match spec.as_slice() {
+                            [_, '<' | '^' | '>', ..] => 2,
+                            ['<' | '^' | '>', ..] => 1,
+                            _ => 0,
+                        };
+                        if matches!(spec.get(flags), Some('+' | '-')) {
+                            flags += 1;
+                        }
+                        if spec.get(flags) != Some(&'#') {
+                            placeholder = format!(
+                                "{}:{}#{}",
+                                name,
+                                spec[..flags].iter().collect::<String>(),
+                                spec[flags..].iter().collect::<String>()
+                            );
+                            changed = true;
+                        }
+                    }
+                }
+                format.push_str(&placeholder);
+                format.push('}');
+            }
+            _ => {}
+        }
+    }
+
+    changed.then(|| LitStr::new(&format, lit.span()))
+}
+
+fn strip_references(ty: &Type) -> &Type {
+    match ty {
+        Type::Reference(TypeReference { elem, .. })
+        | Type::Paren(TypeParen { elem, .. })
+        | Type::Group(TypeGroup { elem, .. }) => strip_references(elem),
+        _ => ty,
+    }
+}
+
+fn is_string_type(ty: &Type) -> bool {
+    let Type::Path(TypePath { qself: None, path }) = strip_references(ty) else {
+        return false;
+    };
+    let Some(ps) = path.segments.last() else {
+        return false;
+    };
+    match &ps.arguments {
+        PathArguments::None => ps.ident == "String" || ps.ident == "str",
+        PathArguments::AngleBracketed(ab) => {
+            (ps.ident == "Box" || ps.ident == "Cow" || ps.ident == "Rc" || ps.ident == "Arc")
+                && ab
+                    .args
+                    .iter()
+                    .filter_map(|a| wrap_match!(a => GenericArgument::Type))
+                    .any(is_string_type)
+        }
+        PathArguments::Parenthesized(_) => false,
+    }
+}
+
+fn is_integer_type(ty: &Type, target_generics_idents: &HashSet<CompIdent>) -> bool {
+    get_generic_param_ident(strip_references(ty), target_generics_idents).is_some()
+        || is_primitive_integer(ty)
+}
+
+fn is_primitive_integer(ty: &Type) -> bool {
+    let Type::Path(TypePath { qself: None, path }) = strip_references(ty) else {
+        return false;
+    };
+    match path.segments.last() {
+        Some(ps) => matches!(
+            ps.ident.to_string().as_str(),
+            "u8" | "u16"
+                | "u32"
+                | "u64"
+                | "u128"
+                | "usize"
+                | "i8"
+                | "i16"
+                | "i32"
+                | "i64"
+                | "i128"
+                | "isize"
+        ),
+        None => false,
+    }
 }
 
 fn get_generic_param_idents(generics: &Generics) -> HashSet<CompIdent> {
@@ -118,30 +866,21 @@ fn get_generic_param_idents(generics: &Generics) -> HashSet<CompIdent> {
         .collect()
 }
 
-struct CompPath<'a>(&'a Path);
+struct CompType<'a>(&'a Type);
 
-impl<'a> PartialEq<Self> for CompPath<'a> {
+impl<'a> PartialEq<Self> for CompType<'a> {
     fn eq(&self, other: &Self) -> bool {
-        let s: Vec<_> = self.0.segments.iter().rev().collect();
-        let o: Vec<_> = other.0.segments.iter().rev().collect();
-
-        for (s, o) in s.iter().zip(o) {
-            if s.ident.to_string() != o.ident.to_string() {
-                return false;
-            }
-        }
-
-        true
+        let (s, o) = (self.0, other.0);
+        quote!(#s).to_string() == quote!(#o).to_string()
     }
 }
 
-impl<'a> Eq for CompPath<'a> {}
+impl<'a> Eq for CompType<'a> {}
 
-impl<'a> Hash for CompPath<'a> {
+impl<'a> Hash for CompType<'a> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        for seg in &self.0.segments {
-            seg.ident.to_string().hash(state);
-        }
+        let s = self.0;
+        quote!(#s).to_string().hash(state);
     }
 }
 
@@ -149,7 +888,7 @@ struct CompIdent(Ident);
 
 impl PartialEq<Self> for CompIdent {
     fn eq(&self, other: &Self) -> bool {
-        self.0.to_string().eq(&other.0.to_string())
+        self.0 == other.0
     }
 }
 
@@ -161,45 +900,64 @@ impl Hash for CompIdent {
     }
 }
 
+fn add_inferred_bounds<'a: 'b, 'b>(
+    generics: Generics,
+    target_generics_idents: &HashSet<CompIdent>,
+    field_uses: impl IntoIterator<Item = (FormatTrait, &'b [CompType<'a>])>,
+    krate: &Path,
+) -> Generics {
+    let mut used_type_params = Vec::<(FormatTrait, HashSet<CompType>)>::new();
+    for (format_trait, valid_types) in field_uses {
+        let valid_types = valid_types.iter().map(|CompType(ty)| CompType(ty));
+        match used_type_params
+            .iter_mut()
+            .find(|(t, _)| *t == format_trait)
+        {
+            Some((_, used)) => used.extend(valid_types),
+            None => used_type_params.push((format_trait, valid_types.collect())),
+        }
+    }
+
+    used_type_params
+        .iter()
+        .fold(generics, |generics, (format_trait, used_type_params)| {
+            add_trait_bounds(
+                generics,
+                target_generics_idents,
+                used_type_params,
+                &format_trait.path(krate),
+            )
+        })
+}
+
 fn add_trait_bounds(
     mut generics: Generics,
     target_generics_idents: &HashSet<CompIdent>,
-    used_type_params: &HashSet<CompPath>,
+    used_types: &HashSet<CompType>,
+    bound: &Path,
 ) -> Generics {
-    let used_generics_names: HashSet<_> = target_generics_idents
-        .into_iter()
-        .filter(|CompIdent(ident)| {
-            for CompPath(path) in used_type_params {
-                if path.segments.len() == 1 {
-                    if path.segments.first().unwrap().ident.to_string() == ident.to_string() {
-                        return true;
-                    }
-                }
+    let mut used_generics_names = HashSet::new();
+    let mut bounded_types = vec![];
+    for CompType(ty) in used_types {
+        match get_generic_param_ident(ty, target_generics_idents) {
+            Some(ident) => {
+                used_generics_names.insert(CompIdent(ident.clone()));
             }
-
-            false
-        })
-        .collect();
+            None => bounded_types.push(ty),
+        }
+    }
 
     for param in &mut generics.params {
         if let GenericParam::Type(ref mut type_param) = *param {
             if used_generics_names.contains(&CompIdent(type_param.ident.clone())) {
-                type_param.bounds.push(parse_quote!(std::fmt::Debug));
+                type_param.bounds.push(parse_quote!(#bound));
             }
         }
     }
 
-    let assoc_target_names: Vec<_> = used_type_params
-        .iter()
-        .filter(|CompPath(path)| match path.segments.first() {
-            Some(s) if target_generics_idents.contains(&CompIdent(s.ident.clone())) => true,
-            _ => false,
-        })
-        .collect();
-
     let punctuated = &mut generics.make_where_clause().predicates;
-    for CompPath(path) in assoc_target_names {
-        punctuated.push(parse_quote!(#path: std::fmt::Debug));
+    for ty in bounded_types {
+        punctuated.push(parse_quote!(#ty: #bound));
     }
 
     generics
@@ -207,7 +965,7 @@ fn add_trait_bounds(
 
 fn add_custom_bounds(
     mut generics: Generics,
-    target_custom_where_predicates: &Vec<WherePredicate>,
+    target_custom_where_predicates: &[WherePredicate],
 ) -> Generics {
     let punctuated = &mut generics.make_where_clause().predicates;
 
@@ -218,47 +976,101 @@ fn add_custom_bounds(
     generics
 }
 
-fn get_valid_types(ty: &Type) -> Vec<CompPath> {
-    return get_valid_types_inner(ty).unwrap_or(vec![]);
+fn get_generic_param_ident<'a>(
+    ty: &'a Type,
+    target_generics_idents: &HashSet<CompIdent>,
+) -> Option<&'a Ident> {
+    let TypePath { qself: None, path } = wrap_match!(ty => Type::Path)? else {
+        return None;
+    };
+    let ident = path.get_ident()?;
+    target_generics_idents
+        .contains(&CompIdent(ident.clone()))
+        .then_some(ident)
+}
 
-    fn get_valid_types_inner(ty: &Type) -> Option<Vec<CompPath>> {
-        let tp = match ty {
-            Type::Path(tp) => tp,
-            Type::Reference(tr) => wrap_match!(tr.elem.as_ref() => Type::Path)?,
-            _ => return None,
-        };
-        let path = &tp.path;
-        let ps = path.segments.last()?; // naive
-        match ps.ident.to_string().as_str() {
-            "PhantomData" => None,
-            _ => match &ps.arguments {
-                PathArguments::AngleBracketed(ab) => {
-                    let mut v = vec![CompPath(path)];
-                    v.extend(
-                        ab.args
-                            .iter()
-                            .filter_map(|a| {
-                                get_valid_types_inner(wrap_match!(a => GenericArgument::Type)?)
-                            })
-                            .flatten(),
-                    );
-                    v
-                }
-                .into(),
-                PathArguments::Parenthesized(_) => unimplemented!(),
-                PathArguments::None => vec![CompPath(path)].into(),
-            },
-        }
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { path, .. }) => match path.segments.last() {
+            Some(ps) => ps.ident == "PhantomData",
+            None => false,
+        },
+        _ => false,
     }
 }
 
-fn get_debug_format(attrs: &Vec<Attribute>) -> Option<String> {
-    let attr = attrs.first()?;
-    let meta = attr.parse_meta().ok()?;
-    let nv = wrap_match!(meta => Meta::NameValue)?;
-    match nv.path.get_ident()?.to_string().as_str() {
-        "debug" => wrap_match!(nv.lit => Lit::Str)?.value().into(),
-        _ => None,
+fn mentions_generics(ty: &Type, target_generics_idents: &HashSet<CompIdent>) -> bool {
+    return mentions(quote!(#ty), target_generics_idents);
+
+    fn mentions(ts: TokenStream2, target_generics_idents: &HashSet<CompIdent>) -> bool {
+        ts.into_iter().any(|tt| match tt {
+            TokenTree::Ident(ident) => target_generics_idents.contains(&CompIdent(ident)),
+            TokenTree::Group(g) => mentions(g.stream(), target_generics_idents),
+            _ => false,
+        })
+    }
+}
+
+fn get_valid_types<'a>(
+    ty: &'a Type,
+    target_generics_idents: &HashSet<CompIdent>,
+) -> Vec<CompType<'a>> {
+    let mut valid_types = vec![];
+    collect_valid_types(ty, target_generics_idents, &mut valid_types);
+    return valid_types;
+
+    fn collect_valid_types<'a>(
+        ty: &'a Type,
+        target_generics_idents: &HashSet<CompIdent>,
+        valid_types: &mut Vec<CompType<'a>>,
+    ) {
+        match ty {
+            Type::Path(TypePath { qself: Some(_), .. })
+                if mentions_generics(ty, target_generics_idents) =>
+            {
+                valid_types.push(CompType(ty));
+            }
+            Type::Path(TypePath { qself: None, path }) => {
+                let Some(first) = path.segments.first() else {
+                    return;
+                };
+                if path.leading_colon.is_none()
+                    && target_generics_idents.contains(&CompIdent(first.ident.clone()))
+                {
+                    valid_types.push(CompType(ty));
+                    return;
+                }
+                if is_phantom_data(ty) {
+                    return;
+                }
+                for ps in &path.segments {
+                    if let PathArguments::AngleBracketed(ab) = &ps.arguments {
+                        for ty in ab
+                            .args
+                            .iter()
+                            .filter_map(|a| wrap_match!(a => GenericArgument::Type))
+                        {
+                            collect_valid_types(ty, target_generics_idents, valid_types);
+                        }
+                    }
+                }
+            }
+            Type::Reference(TypeReference { elem, .. })
+            | Type::Array(TypeArray { elem, .. })
+            | Type::Slice(TypeSlice { elem, .. })
+            | Type::Paren(TypeParen { elem, .. })
+            | Type::Group(TypeGroup { elem, .. }) => {
+                collect_valid_types(elem, target_generics_idents, valid_types)
+            }
+            Type::Tuple(TypeTuple { elems, .. }) => {
+                for elem in elems {
+                    collect_valid_types(elem, target_generics_idents, valid_types);
+                }
+            }
+            // No bound on a type parameter can make raw pointers, function pointers or trait
+            // objects implement Debug, so they add none.
+            _ => {}
+        }
     }
 }
 
@@ -274,37 +1086,422 @@ macro_rules! wrap_match {
 
 use wrap_match;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FormatTrait {
+    Display,
+    Debug,
+    LowerHex,
+    UpperHex,
+    Octal,
+    Binary,
+    LowerExp,
+    UpperExp,
+    Pointer,
+}
+
+impl FormatTrait {
+    fn from_spec(spec: &str) -> Self {
+        match spec.chars().last() {
+            Some('?') => FormatTrait::Debug,
+            Some('x') => FormatTrait::LowerHex,
+            Some('X') => FormatTrait::UpperHex,
+            Some('o') => FormatTrait::Octal,
+            Some('b') => FormatTrait::Binary,
+            Some('e') => FormatTrait::LowerExp,
+            Some('E') => FormatTrait::UpperExp,
+            Some('p') => FormatTrait::Pointer,
+            _ => FormatTrait::Display,
+        }
+    }
+
+    fn path(self, krate: &Path) -> Path {
+        match self {
+            FormatTrait::Display => parse_quote!(#krate::fmt::Display),
+            FormatTrait::Debug => parse_quote!(#krate::fmt::Debug),
+            FormatTrait::LowerHex => parse_quote!(#krate::fmt::LowerHex),
+            FormatTrait::UpperHex => parse_quote!(#krate::fmt::UpperHex),
+            FormatTrait::Octal => parse_quote!(#krate::fmt::Octal),
+            FormatTrait::Binary => parse_quote!(#krate::fmt::Binary),
+            FormatTrait::LowerExp => parse_quote!(#krate::fmt::LowerExp),
+            FormatTrait::UpperExp => parse_quote!(#krate::fmt::UpperExp),
+            FormatTrait::Pointer => parse_quote!(#krate::fmt::Pointer),
+        }
+    }
+}
+
+struct FormatTemplate {
+    format: LitStr,
+    args: Vec<FormatArg>,
+}
+
+struct FormatArg {
+    index: usize,
+    traits: Vec<FormatTrait>,
+    by_value: bool,
+}
+
+impl FormatArg {
+    fn name(&self) -> Ident {
+        Ident::new(&format!("__field{}", self.index), Span::call_site())
+    }
+}
+
+fn parse_format_template(lit: &LitStr, fields: &Fields) -> syn::Result<FormatTemplate> {
+    let source = lit.value();
+    let mut format = String::new();
+    let mut args = Vec::<FormatArg>::new();
+
+    let mut use_field = |name: &str, format_trait: Option<FormatTrait>| -> syn::Result<Ident> {
+        let index = fields
+            .iter()
+            .enumerate()
+            .position(|(i, f)| match &f.ident {
+                Some(ident) => ident == name,
+                None => i.to_string() == name,
+            })
+            .ok_or_else(|| match name {
+                "" => syn::Error::new_spanned(
+                    lit,
+                    "implicit positional arguments are not supported, refer to a field by name or index",
+                ),
+                _ => syn::Error::new_spanned(
+                    lit,
+                    format!("unknown field `{}` in format string", name),
+                ),
+            })?;
+
+        let arg = match args.iter().position(|a| a.index == index) {
+            Some(position) => &mut args[position],
+            None => {
+                args.push(FormatArg {
+                    index,
+                    traits: vec![],
+                    by_value: false,
+                });
+                args.last_mut().unwrap()
+            }
+        };
+        match format_trait {
+            Some(format_trait) if !arg.traits.contains(&format_trait) => {
+                arg.traits.push(format_trait)
+            }
+            Some(_) => {}
+            None => arg.by_value = true,
+        }
+
+        Ok(arg.name())
+    };
+
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => format.push_str("{{"),
+            '}' if chars.next_if_eq(&'}').is_some() => format.push_str("}}"),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "unterminated `{` in format string",
+                            ))
+                        }
+                    }
+                }
+                let (name, spec) = match placeholder.split_once(':') {
+                    Some((name, spec)) => (name.trim(), spec),
+                    None => (placeholder.trim(), ""),
+                };
+
+                let arg = use_field(name, Some(FormatTrait::from_spec(spec)))?;
+                format.push('{');
+                format.push_str(&arg.to_string());
+                if !spec.is_empty() {
+                    format.push(':');
+                    let mut word = String::new();
+                    for c in spec.chars() {
+                        match c {
+                            '$' => {
+                                format.push_str(&use_field(&word, None)?.to_string());
+                                format.push('$');
+                                word.clear();
+                            }
+                            '*' => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "`.*` precision is not supported, refer to a field with `.field$` instead",
+                                ))
+                            }
+                            c if c.is_alphanumeric() || c == '_' => word.push(c),
+                            c => {
+                                format.push_str(&word);
+                                format.push(c);
+                                word.clear();
+                            }
+                        }
+                    }
+                    format.push_str(&word);
+                }
+                format.push('}');
+            }
+            '}' => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "unmatched `}` in format string",
+                ))
+            }
+            c => format.push(c),
+        }
+    }
+
+    Ok(FormatTemplate {
+        format: LitStr::new(&format, lit.span()),
+        args,
+    })
+}
+
 fn render(
     target_ident: &Ident,
     generics: &Generics,
-    field_debug_data: &Vec<Option<FieldDebugInfo>>,
+    fields: &Fields,
+    target_debug_info: &TargetDebugInfo,
+    field_debug_data: &[FieldDebugInfo],
+    body: &FmtBody,
 ) -> TokenStream2 {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    let field_supplies = field_debug_data.iter().filter_map(|f| {
-        f.as_ref().map(
-            |FieldDebugInfo {
-                 ident,
-                 debug_format,
-             }| {
-                match debug_format {
-                    Some(debug_format) => quote! {
-                        .field(stringify!(#ident), &format_args!(#debug_format, &self.#ident))
+    let target_name = target_debug_info.name(target_ident);
+    let krate = &target_debug_info.krate;
+
+    let mut fields_helper = None;
+
+    let body = match body {
+        FmtBody::Fields => {
+            let field_supplies = field_debug_data.iter().filter(|f| !f.skip).map(|field| {
+                let FieldDebugInfo {
+                    member, ty, format, ..
+                } = field;
+                let value = render_field_value(member, format, krate);
+                match (format, field.name(target_debug_info.rename_all)) {
+                    // Spanned so that a type without the helper is reported at
+                    // the flattened field.
+                    (FieldFormat::Flatten(_), _) => quote_spanned! {ty.span()=>
+                        self.#member.__fmt_debug_fields(debug);
+                    },
+                    (_, Some(name)) => quote! {
+                        debug.field(#name, #value);
                     },
-                    None => quote! {
-                        .field(stringify!(#ident), &self.#ident)
+                    (_, None) => quote! {
+                        debug.field(#value);
                     },
                 }
+            });
+
+            let debug_with = field_debug_data
+                .iter()
+                .any(|f| !f.skip && f.format.uses_debug_with())
+                .then(|| render_debug_with(krate));
+
+            match fields {
+                Fields::Unnamed(_) => quote! {
+                    #debug_with
+                    let debug = &mut f.debug_tuple(#target_name);
+                    #(#field_supplies)*
+                    debug.finish()
+                },
+                _ => {
+                    fields_helper = Some(quote! {
+                        impl #impl_generics #target_ident #ty_generics #where_clause {
+                            #[doc(hidden)]
+                            pub fn __fmt_debug_fields(&self, debug: &mut #krate::fmt::DebugStruct<'_, '_>) {
+                                #debug_with
+                                #(#field_supplies)*
+                            }
+                        }
+                    });
+                    quote! {
+                        let debug = &mut f.debug_struct(#target_name);
+                        self.__fmt_debug_fields(debug);
+                        debug.finish()
+                    }
+                }
+            }
+        }
+        FmtBody::Transparent(i) => {
+            let FieldDebugInfo { member, format, .. } = &field_debug_data[*i];
+            let debug_with = format.uses_debug_with().then(|| render_debug_with(krate));
+            let value = render_field_value(member, format, krate);
+            quote! {
+                #debug_with
+                #krate::fmt::Debug::fmt(#value, f)
+            }
+        }
+        FmtBody::Template(FormatTemplate { format, args }) => {
+            let args = args.iter().map(|arg| {
+                let name = arg.name();
+                let member = &field_debug_data[arg.index].member;
+                if arg.by_value {
+                    quote!(#name = self.#member)
+                } else {
+                    quote!(#name = &self.#member)
+                }
+            });
+            quote! {
+                f.write_fmt(format_args!(#format, #(#args),*))
+            }
+        }
+    };
+
+    quote! {
+        impl #impl_generics #krate::fmt::Debug for #target_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut #krate::fmt::Formatter<'_>) -> #krate::result::Result<(), #krate::fmt::Error> {
+                #body
+            }
+        }
+
+        #fields_helper
+    }
+}
+
+fn render_debug_with(krate: &Path) -> TokenStream2 {
+    quote! {
+        struct __DebugWith<F: Fn(&mut #krate::fmt::Formatter<'_>) -> #krate::fmt::Result>(F);
+
+        impl<F: Fn(&mut #krate::fmt::Formatter<'_>) -> #krate::fmt::Result> #krate::fmt::Debug for __DebugWith<F> {
+            fn fmt(&self, f: &mut #krate::fmt::Formatter<'_>) -> #krate::fmt::Result {
+                (self.0)(f)
+            }
+        }
+    }
+}
+
+fn render_reflection(
+    target_ident: &Ident,
+    generics: &Generics,
+    fields_generics: &Generics,
+    target_debug_info: &TargetDebugInfo,
+    field_debug_data: &[FieldDebugInfo],
+) -> TokenStream2 {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (fields_impl_generics, _, fields_where_clause) = fields_generics.split_for_impl();
+    let krate = &target_debug_info.krate;
+
+    let fields: Vec<_> = field_debug_data.iter().filter(|f| !f.skip).collect();
+    let len = fields.len();
+    let names: Vec<_> = fields
+        .iter()
+        .map(
+            |field| match (&field.member, field.name(target_debug_info.rename_all)) {
+                (_, Some(name)) => name,
+                (Member::Unnamed(index), None) => index.index.to_string(),
+                (Member::Named(_), None) => unreachable!(),
             },
         )
-    });
+        .collect();
+    let members = fields.iter().map(|field| &field.member);
 
     quote! {
-        impl #impl_generics std::fmt::Debug for #target_ident #ty_generics #where_clause {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-                f.debug_struct(stringify!(#target_ident))
-                    #(#field_supplies)*
-                    .finish()
+        impl #impl_generics #target_ident #ty_generics #where_clause {
+            pub const FIELD_NAMES: &'static [&'static str] = &[#(#names),*];
+        }
+
+        impl #fields_impl_generics #target_ident #ty_generics #fields_where_clause {
+            pub fn debug_fields(&self) -> impl Iterator<Item = (&'static str, &dyn #krate::fmt::Debug)> + '_ {
+                let fields: [(&'static str, &dyn #krate::fmt::Debug); #len] = [
+                    #((#names, &self.#members),)*
+                ];
+                #krate::iter::IntoIterator::into_iter(fields)
             }
         }
     }
 }
+
+fn render_field_value(member: &Member, format: &FieldFormat, krate: &Path) -> TokenStream2 {
+    match format {
+        FieldFormat::Debug | FieldFormat::Flatten(_) => quote!(&self.#member),
+        FieldFormat::Custom { fmt, alt: None } => quote! {
+            &format_args!(#fmt, &self.#member)
+        },
+        FieldFormat::Custom {
+            fmt,
+            alt: Some(alt),
+        } => quote! {
+            &__DebugWith(|f| {
+                if f.alternate() {
+                    f.write_fmt(format_args!(#alt, &self.#member))
+                } else {
+                    f.write_fmt(format_args!(#fmt, &self.#member))
+                }
+            })
+        },
+        FieldFormat::Hex => quote! {
+            &format_args!("{:#x}", &self.#member)
+        },
+        FieldFormat::Str { truncate } => quote! {
+            &__DebugWith(|f| {
+                let s: &str = &self.#member;
+                match s.char_indices().nth(#truncate) {
+                    Some((i, _)) => f.write_fmt(format_args!(
+                        "{:?}… {} more",
+                        &s[..i],
+                        s[i..].chars().count()
+                    )),
+                    None => #krate::fmt::Debug::fmt(s, f),
+                }
+            })
+        },
+        FieldFormat::List { hex, truncate } => {
+            let entries = if *hex {
+                quote! {
+                    .map(|v| __DebugWith(move |f| f.write_fmt(format_args!("{:#x}", v))))
+                }
+            } else {
+                quote!()
+            };
+            match truncate {
+                Some(truncate) => quote! {
+                    &__DebugWith(|f| {
+                        let mut iter = (&self.#member).into_iter();
+                        let mut list = f.debug_list();
+                        list.entries(iter.by_ref().take(#truncate) #entries);
+                        let rest = iter.count();
+                        if rest > 0 {
+                            list.entry(&format_args!("… {} more", rest));
+                        }
+                        list.finish()
+                    })
+                },
+                None => quote! {
+                    &__DebugWith(|f| {
+                        f.debug_list()
+                            .entries((&self.#member).into_iter() #entries)
+                            .finish()
+                    })
+                },
+            }
+        }
+        FieldFormat::Map { truncate } => match truncate {
+            Some(truncate) => quote! {
+                &__DebugWith(|f| {
+                    let mut iter = (&self.#member).into_iter();
+                    let mut map = f.debug_map();
+                    map.entries(iter.by_ref().take(#truncate).map(|(k, v)| (k, v)));
+                    let rest = iter.count();
+                    if rest > 0 {
+                        map.entry(&format_args!("…"), &format_args!("{} more", rest));
+                    }
+                    map.finish()
+                })
+            },
+            None => quote! {
+                &__DebugWith(|f| {
+                    f.debug_map()
+                        .entries((&self.#member).into_iter().map(|(k, v)| (k, v)))
+                        .finish()
+                })
+            },
+        },
+    }
+}
@@ -0,0 +1,91 @@
+// Small value types such as identifiers and coordinates read better without
+// the full `Name { field: value }` layout.
+//
+// A struct-level #[debug(transparent)] forwards Debug to the single field of a
+// newtype, ignoring skipped and PhantomData fields and keeping the format
+// given on that field. #[debug("...")] (or #[debug(fmt = "...")]) replaces the
+// whole output with a format string that refers to fields by name or by index:
+//
+//     impl Debug for Point {
+//         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+//             f.write_fmt(format_args!("Point({}, {})", self.x, self.y))
+//         }
+//     }
+//
+// Only the fields mentioned in the format string need to satisfy the trait
+// selected by their format spec, so `{x}` asks for Display and `{x:?}` for
+// Debug.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Id(u64);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Name<T> {
+    inner: T,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct TypedId<T>(#[debug(hex)] u64, PhantomData<T>);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Label {
+    #[debug(skip)]
+    cache: usize,
+    #[debug(truncate = 3)]
+    text: String,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Celsius(#[debug("{:.1}°C")] f64);
+
+#[derive(CustomDebug)]
+#[debug("Point({x}, {y})")]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+#[debug(fmt = "#{0:04x} {1:?} {{{2:>0$}}}")]
+pub struct Tagged<T, U>(usize, T, U, PhantomData<U>);
+
+#[derive(CustomDebug)]
+#[debug("{left}")]
+pub struct Pair<L, R> {
+    left: L,
+    right: R,
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+
+    assert_eq!(format!("{:?}", Id(7)), "7");
+    assert_eq!(format!("{:?}", Name { inner: "n" }), r#""n""#);
+    assert_eq!(format!("{:?}", TypedId::<NotDebug>(255, PhantomData)), "0xff");
+    let label = Label {
+        cache: 0,
+        text: "label".to_owned(),
+    };
+    assert_eq!(format!("{:?}", label), r#""lab"… 2 more"#);
+    assert_eq!(format!("{:?}", Celsius(21.0)), "21.0°C");
+    assert_eq!(format!("{:?}", Point { x: 1, y: -2 }), "Point(1, -2)");
+    assert_eq!(
+        format!("{:?}", Tagged(5, Some('a'), 9, PhantomData)),
+        "#0005 Some('a') {    9}"
+    );
+    assert_eq!(format!("{:?}", Pair { left: 3, right: () }), "3");
+
+    assert_debug::<Tagged<Option<u8>, &str>>();
+    assert_debug::<Pair<u8, NotDebug>>();
+}
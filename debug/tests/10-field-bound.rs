@@ -0,0 +1,68 @@
+// The escape hatch from the previous test also accepts a comma separated list
+// of predicates, including an empty one, and can be attached to individual
+// fields. A field-level bound replaces only the bounds inferred from that
+// field's type while inference keeps working for the other fields:
+//
+//     impl<T: Trait, U: Debug> Debug for Wrapper<T, U>
+//     where
+//         <T as Trait>::Value: Debug,
+//     {...}
+
+use derive_debug::CustomDebug;
+use std::fmt::{self, Debug};
+
+pub trait Trait {
+    type Value;
+}
+
+#[derive(CustomDebug)]
+pub struct Wrapper<T: Trait, U> {
+    #[debug(bound = "<T as Trait>::Value: Debug,")]
+    field: Field<T>,
+    normal: U,
+    #[debug(bound = "")]
+    marker: Marker<T>,
+}
+
+#[derive(CustomDebug)]
+#[debug(bound = "T::Value: Debug, U: Clone + Debug")]
+pub struct Both<T: Trait, U> {
+    field: Field<T>,
+    other: U,
+}
+
+#[derive(CustomDebug)]
+struct Field<T: Trait> {
+    values: Vec<T::Value>,
+}
+
+pub struct Marker<T>(Option<T>);
+
+impl<T> Debug for Marker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Marker")
+    }
+}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct Id;
+
+    impl Trait for Id {
+        type Value = u8;
+    }
+
+    assert_debug::<Wrapper<Id, String>>();
+    assert_debug::<Both<Id, String>>();
+
+    let wrapper = Wrapper {
+        field: Field::<Id> { values: vec![1] },
+        normal: 'n',
+        marker: Marker(None),
+    };
+    assert_eq!(
+        format!("{:?}", wrapper),
+        "Wrapper { field: Field { values: [1] }, normal: 'n', marker: Marker }"
+    );
+}
@@ -0,0 +1,106 @@
+// Checks bound inference on every kind of type a field can have, not only
+// paths and references to paths. The element types of tuples, arrays, slices
+// and nested references get a Debug bound. Type parameters that only appear in
+// function pointers, raw pointers or trait objects get none, so `Forms` is
+// Debug with NotDebug for A and B. Qualified paths such as <T as Trait>::Assoc
+// are bounded in the where clause just like T::Assoc, including when the type
+// parameter is a trait argument as in `Converted`.
+//
+//     impl<'a, A, B, C: Debug, D: Debug, E: Debug, G: Trait> Debug for Forms<'a, A, B, C, D, E, G>
+//     where
+//         <G as Trait>::Assoc: Debug,
+//         G::Item: Debug,
+//     {...}
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+pub trait Trait {
+    type Assoc;
+    type Item;
+}
+
+pub trait Convert<T> {
+    type Output;
+}
+
+impl Convert<u8> for Vec<u8> {
+    type Output = char;
+}
+
+pub trait Callback<A>: Debug {}
+
+#[derive(CustomDebug)]
+pub struct Forms<'a, A, B, C, D, E, G: Trait> {
+    callback: fn(A) -> B,
+    optional_callback: Option<fn(A)>,
+    boxed: Box<dyn Callback<A>>,
+    pointer: *const B,
+    tuple: (C, u8),
+    array: [D; 2],
+    slice: &'a [E],
+    nested: &'a &'a mut Option<E>,
+    qualified: <G as Trait>::Assoc,
+    item: Vec<G::Item>,
+}
+
+#[derive(CustomDebug)]
+pub struct Converted<T>
+where
+    Vec<u8>: Convert<T>,
+{
+    output: <Vec<u8> as Convert<T>>::Output,
+}
+
+#[derive(CustomDebug)]
+pub struct SameName<T: Iterator> {
+    item: Item,
+    other: T::Item,
+}
+
+#[derive(Debug)]
+pub struct Item;
+
+#[derive(Debug)]
+struct Noop;
+
+impl<A> Callback<A> for Noop {}
+
+fn assert_debug<F: Debug>() {}
+
+fn main() {
+    struct NotDebug;
+    struct Id;
+
+    impl Trait for Id {
+        type Assoc = u8;
+        type Item = u16;
+    }
+
+    assert_debug::<Forms<NotDebug, NotDebug, u8, u8, u8, Id>>();
+    assert_debug::<Converted<u8>>();
+    assert_debug::<SameName<std::vec::IntoIter<u8>>>();
+
+    let value = 0;
+    let mut option = Some(-2);
+    let forms = Forms::<u8, u8, char, bool, i8, Id> {
+        callback: |_| 0,
+        optional_callback: None,
+        boxed: Box::new(Noop),
+        pointer: &value,
+        tuple: ('c', 1),
+        array: [true, false],
+        slice: &[-1],
+        nested: &&mut option,
+        qualified: 2,
+        item: vec![3],
+    };
+    let converted = Converted::<u8> { output: 'd' };
+    assert_eq!(format!("{:?}", converted), "Converted { output: 'd' }");
+
+    let debug = format!("{:?}", forms);
+    assert!(debug.contains("optional_callback: None, boxed: Noop, pointer: 0x"));
+    assert!(debug.ends_with(
+        "tuple: ('c', 1), array: [true, false], slice: [-1], nested: Some(-2), qualified: 2, item: [3] }"
+    ));
+}
@@ -0,0 +1,34 @@
+// CustomDebug only looks at `debug` attributes. Any other attribute on the
+// struct or on its fields, including doc comments, belongs to someone else and
+// must be left alone. A field may also carry several `debug` attributes, all of
+// which are read.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+
+/// A register with documentation.
+#[derive(CustomDebug)]
+#[allow(dead_code)]
+#[debug(bound = "T: Debug")]
+#[must_use]
+pub struct Register<T> {
+    /// The documented mask.
+    #[allow(unused)]
+    #[debug = "0b{:08b}"]
+    #[debug(bound = "")]
+    mask: u8,
+    #[cfg_attr(any(), debug = "never used")]
+    value: T,
+}
+
+fn main() {
+    let register = Register {
+        mask: 0b1010,
+        value: "v",
+    };
+
+    assert_eq!(
+        format!("{:?}", register),
+        r#"Register { mask: 0b00001010, value: "v" }"#
+    );
+}
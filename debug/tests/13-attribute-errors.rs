@@ -0,0 +1,22 @@
+// Malformed `debug` attributes are reported together, each one pointing at the
+// part of the attribute that is wrong.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(transparent = "yes", fmt = 1)]
+#[debug(unknown)]
+pub struct Struct {
+    #[debug = 8]
+    first: u8,
+    #[debug(bound = "T Debug")]
+    second: u8,
+    #[debug("{:?}", fmt = "{:x}")]
+    third: u8,
+    #[debug]
+    fourth: u8,
+    #[debug(alt = "{:#?}")]
+    fifth: u8,
+}
+
+fn main() {}
@@ -0,0 +1,94 @@
+// Large buffers and tables make `{:?}` output unreadable, so fields can opt
+// into more compact renderings:
+//
+//   - #[debug(hex)] prints an integer as `0x1f`, or every element of a byte
+//     buffer or other integer sequence in hex.
+//   - #[debug(truncate = N)] keeps the first N elements of a sequence, or the
+//     first N characters of a string, followed by `… M more`. Strings are
+//     `String` and `str`, also behind references, `Box`, `Rc`, `Arc` and `Cow`.
+//   - #[debug(as_list)] and #[debug(as_map)] render any type whose reference
+//     can be iterated through `debug_list` and `debug_map`, which is handy for
+//     wrappers that do not implement Debug themselves.
+
+use derive_debug::CustomDebug;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+pub struct Table(Vec<(&'static str, u32)>);
+
+impl<'a> IntoIterator for &'a Table {
+    type Item = &'a (&'static str, u32);
+    type IntoIter = std::slice::Iter<'a, (&'static str, u32)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[derive(CustomDebug)]
+pub struct Packet<'a, T> {
+    #[debug(hex)]
+    id: u16,
+    #[debug(hex)]
+    tag: T,
+    #[debug(hex, truncate = 4)]
+    payload: Vec<u8>,
+    #[debug(hex)]
+    header: &'a [u8; 2],
+    #[debug(truncate = 5)]
+    name: String,
+    #[debug(truncate = 16)]
+    short: &'a str,
+    #[debug(truncate = 2)]
+    values: Vec<T>,
+    #[debug(truncate = 3)]
+    boxed: Box<String>,
+    #[debug(truncate = 3)]
+    shared: Rc<str>,
+    #[debug(truncate = 3)]
+    cow: Cow<'a, str>,
+    #[debug(as_map)]
+    table: Table,
+    #[debug(as_list, truncate = 1)]
+    pairs: Table,
+    #[debug(as_map, truncate = 1)]
+    sorted: BTreeMap<u8, char>,
+}
+
+fn main() {
+    let packet = Packet {
+        id: 0x1f,
+        tag: 255u8,
+        payload: vec![0xde, 0xad, 0xbe, 0xef, 0x00, 0x01],
+        header: &[1, 2],
+        name: "abcdefgh".to_string(),
+        short: "short",
+        values: vec![1, 2, 3],
+        boxed: Box::new("boxed".to_string()),
+        shared: Rc::from("shared"),
+        cow: Cow::Borrowed("cow"),
+        table: Table(vec![("a", 1), ("b", 2)]),
+        pairs: Table(vec![("c", 3), ("d", 4)]),
+        sorted: [(1, 'x'), (2, 'y')].into_iter().collect(),
+    };
+
+    let debug = format!("{:?}", packet);
+    let expected = concat!(
+        "Packet { ",
+        "id: 0x1f, ",
+        "tag: 0xff, ",
+        "payload: [0xde, 0xad, 0xbe, 0xef, … 2 more], ",
+        "header: [0x1, 0x2], ",
+        r#"name: "abcde"… 3 more, "#,
+        r#"short: "short", "#,
+        "values: [1, 2, … 1 more], ",
+        r#"boxed: "box"… 2 more, "#,
+        r#"shared: "sha"… 3 more, "#,
+        r#"cow: "cow", "#,
+        r#"table: {"a": 1, "b": 2}, "#,
+        r#"pairs: [("c", 3), … 1 more], "#,
+        "sorted: {1: 'x', …: 1 more} }",
+    );
+    assert_eq!(debug, expected);
+}
@@ -0,0 +1,61 @@
+// A field marked #[debug(flatten)] whose type also derives CustomDebug has its
+// fields written directly into the parent's output instead of as a nested
+// struct. To make that possible every struct with named fields gets a hidden
+// helper that writes its fields into an existing `DebugStruct`:
+//
+//     impl Common {
+//         #[doc(hidden)]
+//         pub fn __fmt_debug_fields(&self, debug: &mut fmt::DebugStruct) {...}
+//     }
+//
+// Structs whose output is not a list of named fields, that is tuple structs
+// and structs with a struct-level format or #[debug(transparent)], get no
+// helper and cannot be flattened, see 23-flatten-unsupported.rs.
+//
+// The parent infers bounds for a flattened field the same way as for any other
+// field. When the flattened struct needs something else, such as LowerHex for a
+// `hex` field, spell it out with a field-level bound.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Common<T> {
+    id: u32,
+    #[debug(hex)]
+    flags: T,
+}
+
+#[derive(CustomDebug)]
+pub struct Request<T> {
+    #[debug(flatten, bound = "T: std::fmt::LowerHex")]
+    common: Common<T>,
+    path: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub struct Envelope {
+    #[debug(flatten)]
+    request: Request<u8>,
+    #[debug(flatten)]
+    extra: Common<u16>,
+}
+
+fn main() {
+    let request = Request {
+        common: Common { id: 7, flags: 0x10u8 },
+        path: "/",
+    };
+    assert_eq!(
+        format!("{:?}", request),
+        r#"Request { id: 7, flags: 0x10, path: "/" }"#
+    );
+
+    let envelope = Envelope {
+        request,
+        extra: Common { id: 8, flags: 0xff },
+    };
+    assert_eq!(
+        format!("{:?}", envelope),
+        r#"Envelope { id: 7, flags: 0x10, path: "/", id: 8, flags: 0xff }"#
+    );
+}
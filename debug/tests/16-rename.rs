@@ -0,0 +1,40 @@
+// The names printed for the struct and its fields can be changed to match an
+// external naming scheme. #[debug(rename = "...")] works on the struct and on
+// individual fields, and #[debug(rename_all = "...")] converts every field name
+// to one of the usual cases: "lowercase", "UPPERCASE", "PascalCase",
+// "camelCase", "snake_case", "SCREAMING_SNAKE_CASE", "kebab-case" and
+// "SCREAMING-KEBAB-CASE". An explicit field rename wins over rename_all.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(rename = "LoginRequest", rename_all = "camelCase")]
+pub struct Login {
+    user_name: &'static str,
+    r#type: u8,
+    #[debug(rename = "pw")]
+    password_hash: u64,
+}
+
+#[derive(CustomDebug)]
+#[debug(rename_all = "SCREAMING-KEBAB-CASE")]
+pub struct Header {
+    content_length: usize,
+}
+
+fn main() {
+    let login = Login {
+        user_name: "ferris",
+        r#type: 1,
+        password_hash: 42,
+    };
+
+    assert_eq!(
+        format!("{:?}", login),
+        r#"LoginRequest { userName: "ferris", type: 1, pw: 42 }"#
+    );
+    assert_eq!(
+        format!("{:#?}", Header { content_length: 3 }),
+        "Header {\n    CONTENT-LENGTH: 3,\n}"
+    );
+}
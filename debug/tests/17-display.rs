@@ -0,0 +1,72 @@
+// Next to CustomDebug the crate provides a CustomDisplay derive driven by
+// format strings. Structs take a single #[display("...")], enums take one per
+// variant, and unit variants without an attribute print their own name.
+// Fields are referred to by name or index and accept the usual format specs.
+// #[display(transparent)] forwards to the only field.
+//
+// Bounds are inferred like for CustomDebug, except that only the fields the
+// format string mentions are bounded, each by the trait its spec selects.
+
+use derive_debug::CustomDisplay;
+use std::fmt::Display;
+
+#[derive(CustomDisplay)]
+#[display("{host}:{port}")]
+pub struct Address<H> {
+    host: H,
+    port: u16,
+}
+
+#[derive(CustomDisplay)]
+#[display(transparent)]
+pub struct UserId(u64);
+
+#[derive(CustomDisplay)]
+pub enum Error<T, U> {
+    #[display("not found: {0:?}")]
+    NotFound(T),
+    #[display(fmt = "{code:>width$} {reason}")]
+    Status {
+        code: u16,
+        reason: &'static str,
+        width: usize,
+        extra: U,
+    },
+    #[display(transparent)]
+    Other(Box<dyn std::error::Error>),
+    Unknown,
+}
+
+fn assert_display<F: Display>() {}
+
+fn main() {
+    struct NotDisplay;
+
+    assert_eq!(
+        Address {
+            host: "localhost",
+            port: 8080
+        }
+        .to_string(),
+        "localhost:8080"
+    );
+    assert_eq!(UserId(42).to_string(), "42");
+    assert_eq!(
+        Error::<_, ()>::NotFound("key").to_string(),
+        r#"not found: "key""#
+    );
+    assert_eq!(
+        Error::<(), _>::Status {
+            code: 404,
+            reason: "missing",
+            width: 5,
+            extra: NotDisplay,
+        }
+        .to_string(),
+        "  404 missing"
+    );
+    assert_eq!(Error::<(), ()>::Other("boom".into()).to_string(), "boom");
+    assert_eq!(Error::<(), ()>::Unknown.to_string(), "Unknown");
+
+    assert_display::<Error<Vec<u8>, NotDisplay>>();
+}
@@ -0,0 +1,15 @@
+// Mistakes in CustomDisplay attributes are reported at the offending variant
+// or format string.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+pub enum Error {
+    #[display("{missing}")]
+    Unknown { code: u16 },
+    Io(std::io::Error),
+    #[display(transparent)]
+    Pair(u8, u8),
+}
+
+fn main() {}
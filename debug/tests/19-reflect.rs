@@ -0,0 +1,97 @@
+// With #[debug(reflect)] the derive also generates an inherent
+// `FIELD_NAMES` constant and a `debug_fields` method, which yields the same
+// (name, value) pairs that the Debug output is made of. This is meant for
+// structured logging, which wants key/value pairs instead of one string.
+//
+// The names follow rename and rename_all. Fields marked #[debug(skip)] are
+// left out, both here and in the Debug output, as are PhantomData fields.
+// Values are the fields themselves, so formatting options such as `hex` do
+// not apply to them.
+//
+// Only `debug_fields` needs the fields to be Debug, so FIELD_NAMES is there
+// for any type parameters. Bounds given with #[debug(bound = "...")] replace
+// the inferred ones for `debug_fields` as they do for the Debug impl, which
+// is what a recursive type like `Tree` needs.
+
+use derive_debug::CustomDebug;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+#[debug(reflect, rename_all = "camelCase")]
+pub struct Request<T> {
+    user_name: String,
+    #[debug(hex)]
+    flags: u8,
+    #[debug(skip)]
+    token: String,
+    #[debug(rename = "body")]
+    payload: Option<T>,
+    marker: PhantomData<fn() -> T>,
+}
+
+#[derive(CustomDebug)]
+#[debug(reflect)]
+pub struct Pair(u8, &'static str);
+
+#[derive(CustomDebug)]
+#[debug(reflect)]
+pub struct Tree<T> {
+    value: T,
+    #[debug(bound = "T: Debug")]
+    children: Vec<Tree<T>>,
+}
+
+struct NotDebug;
+
+fn collect(fields: impl Iterator<Item = (&'static str, &'static dyn Debug)>) {
+    drop(fields);
+}
+
+fn main() {
+    let request = Request {
+        user_name: "ferris".to_owned(),
+        flags: 10,
+        token: "secret".to_owned(),
+        payload: Some(vec![1, 2]),
+        marker: PhantomData,
+    };
+
+    assert_eq!(
+        format!("{:?}", request),
+        r#"Request { userName: "ferris", flags: 0xa, body: Some([1, 2]) }"#
+    );
+    assert_eq!(
+        Request::<()>::FIELD_NAMES,
+        &["userName", "flags", "body"]
+    );
+
+    let pairs: Vec<_> = request
+        .debug_fields()
+        .map(|(name, value)| format!("{}={:?}", name, value))
+        .collect();
+    assert_eq!(pairs, ["userName=\"ferris\"", "flags=10", "body=Some([1, 2])"]);
+
+    assert_eq!(Pair::FIELD_NAMES, &["0", "1"]);
+    static PAIR: Pair = Pair(1, "one");
+    collect(PAIR.debug_fields());
+
+    assert_eq!(Request::<NotDebug>::FIELD_NAMES, &["userName", "flags", "body"]);
+    assert_eq!(Tree::<NotDebug>::FIELD_NAMES, &["value", "children"]);
+
+    let tree = Tree {
+        value: 1,
+        children: vec![Tree {
+            value: 2,
+            children: vec![],
+        }],
+    };
+    let pairs: Vec<_> = tree
+        .debug_fields()
+        .map(|(name, value)| format!("{}={:?}", name, value))
+        .collect();
+    assert_eq!(
+        pairs,
+        ["value=1", "children=[Tree { value: 2, children: [] }]"]
+    );
+}
@@ -0,0 +1,36 @@
+// A field format can have a second template for the alternate `{:#?}` mode,
+// given as #[debug(fmt = "...", alt = "...")]. Without `alt`, the alternate
+// mode uses the field format with `#` added to its Debug placeholders, so that
+// nested values are pretty-printed along with the rest of the struct.
+
+use derive_debug::CustomDebug;
+
+#[derive(Debug)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+pub struct Shape {
+    #[debug(fmt = "0b{:08b}", alt = "{:#010b} ({0})")]
+    flags: u8,
+    #[debug = "at {:?}"]
+    origin: Point,
+}
+
+fn main() {
+    let shape = Shape {
+        flags: 5,
+        origin: Point { x: 1, y: 2 },
+    };
+
+    assert_eq!(
+        format!("{:?}", shape),
+        "Shape { flags: 0b00000101, origin: at Point { x: 1, y: 2 } }"
+    );
+    assert_eq!(
+        format!("{:#?}", shape),
+        "Shape {\n    flags: 0b00000101 (5),\n    origin: at Point {\n        x: 1,\n        y: 2,\n    },\n}"
+    );
+}
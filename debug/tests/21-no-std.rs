@@ -0,0 +1,47 @@
+// The generated impls only refer to `core`, so both derives work in a
+// #![no_std] crate. If `core` is only reachable through a re-export, its path
+// can be given with #[debug(crate = "...")] or #[display(crate = "...")].
+
+#![no_std]
+
+use derive_debug::{CustomDebug, CustomDisplay};
+
+pub mod support {
+    pub use ::core as core_reexport;
+}
+
+#[derive(CustomDebug, CustomDisplay)]
+#[debug(reflect)]
+#[display("{name} at {address:#x}")]
+pub struct Device<T> {
+    name: &'static str,
+    #[debug(hex)]
+    address: T,
+    #[debug(truncate = 2)]
+    registers: [u8; 4],
+}
+
+#[derive(CustomDebug, CustomDisplay)]
+#[debug(crate = "crate::support::core_reexport", transparent)]
+#[display(crate = "crate::support::core_reexport", transparent)]
+pub struct Id(u32);
+
+fn main() {
+    extern crate std;
+    use std::format;
+
+    let device = Device {
+        name: "uart",
+        address: 0x1000u32,
+        registers: [1, 2, 3, 4],
+    };
+    assert_eq!(
+        format!("{:?}", device),
+        r#"Device { name: "uart", address: 0x1000, registers: [1, 2, … 2 more] }"#
+    );
+    assert_eq!(format!("{}", device), "uart at 0x1000");
+    assert_eq!(device.debug_fields().count(), 3);
+
+    assert_eq!(format!("{:?}", Id(7)), "7");
+    assert_eq!(format!("{}", Id(7)), "7");
+}
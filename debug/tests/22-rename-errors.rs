@@ -0,0 +1,9 @@
+// Fields of a tuple struct are printed without names, so
+// #[debug(rename = "...")] on one of them is reported instead of ignored.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Pair(#[debug(rename = "x")] u8, u8);
+
+fn main() {}
@@ -0,0 +1,35 @@
+// Only structs with named fields and the default output have fields to
+// write into their parent, so flattening a tuple struct, a transparent struct
+// or a struct with its own format string is rejected. The compiler does not
+// find the helper that #[debug(flatten)] calls and reports it at the type of
+// the flattened field.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Pair(u8, u8);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Id {
+    value: u64,
+}
+
+#[derive(CustomDebug)]
+#[debug("({x}, {y})")]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(CustomDebug)]
+pub struct Parent {
+    #[debug(flatten)]
+    pair: Pair,
+    #[debug(flatten)]
+    id: Id,
+    #[debug(flatten)]
+    point: Point,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// Formatting options that cannot apply to the type of the field are reported
+// at the option, rather than left to a trait error in the generated code.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+pub struct Struct {
+    #[debug(truncate = 4)]
+    count: u32,
+    #[debug(hex, truncate = 4)]
+    id: &'static u64,
+    #[debug(hex, truncate = 4)]
+    name: String,
+    #[debug(hex, as_map)]
+    table: Vec<(u8, u8)>,
+}
+
+fn main() {}
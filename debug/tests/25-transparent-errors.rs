@@ -0,0 +1,32 @@
+// #[debug(transparent)] needs exactly one field to forward to, not counting
+// skipped and PhantomData fields, and that field cannot be flattened since
+// there is no struct around it to flatten into.
+
+use derive_debug::CustomDebug;
+use std::marker::PhantomData;
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Pair(u8, u8);
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Empty<T> {
+    #[debug(skip)]
+    cache: u8,
+    marker: PhantomData<T>,
+}
+
+#[derive(CustomDebug)]
+pub struct Inner {
+    value: u8,
+}
+
+#[derive(CustomDebug)]
+#[debug(transparent)]
+pub struct Outer {
+    #[debug(flatten)]
+    inner: Inner,
+}
+
+fn main() {}
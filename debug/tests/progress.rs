@@ -9,4 +9,21 @@ fn tests() {
     t.pass("tests/06-bound-trouble.rs");
     t.pass("tests/07-associated-type.rs");
     t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-struct-format.rs");
+    t.pass("tests/10-field-bound.rs");
+    t.pass("tests/11-type-forms.rs");
+    t.pass("tests/12-foreign-attributes.rs");
+    t.compile_fail("tests/13-attribute-errors.rs");
+    t.pass("tests/14-collection-formats.rs");
+    t.pass("tests/15-flatten.rs");
+    t.pass("tests/16-rename.rs");
+    t.pass("tests/17-display.rs");
+    t.compile_fail("tests/18-display-errors.rs");
+    t.pass("tests/19-reflect.rs");
+    t.pass("tests/20-alternate-format.rs");
+    t.pass("tests/21-no-std.rs");
+    t.compile_fail("tests/22-rename-errors.rs");
+    t.compile_fail("tests/23-flatten-unsupported.rs");
+    t.compile_fail("tests/24-format-option-errors.rs");
+    t.compile_fail("tests/25-transparent-errors.rs");
 }
@@ -0,0 +1,237 @@
+use crate::template::Template;
+use crate::{loop_vars, LoopVar, SeqVar};
+use proc_macro2::{
+    Delimiter, Group, Ident, Punct, Spacing, TokenStream as TokenStream2, TokenTree,
+};
+use quote::{ToTokens, TokenStreamExt};
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Field, Fields, ImplItem, Item, Token, Variant};
+
+/// Expands `#[seq_for(N in 0..8)]` on an enum, a struct or an impl block. Only
+/// the variants, fields or associated items marked `#[seq_each]` are repeated,
+/// the rest of the item is left as it is.
+pub(crate) fn expand(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let header = Punctuated::<SeqVar, Token![,]>::parse_terminated.parse2(args)?;
+    if header.is_empty() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "expected loop variables, as in `#[seq_for(N in 0..8)]`",
+        ));
+    }
+    let vars = loop_vars(&header)?;
+
+    let mut item: Item = syn::parse2(input)?;
+    match &mut item {
+        Item::Enum(item) => {
+            let variants = expand_elements(std::mem::take(&mut item.variants), true, &vars)?;
+            item.variants = Punctuated::parse_terminated.parse2(variants)?;
+        }
+        Item::Struct(item) => match &mut item.fields {
+            Fields::Named(fields) => {
+                let named = expand_elements(std::mem::take(&mut fields.named), true, &vars)?;
+                fields.named = (|input: ParseStream| {
+                    Punctuated::parse_terminated_with(input, Field::parse_named)
+                })
+                .parse2(named)?;
+            }
+            Fields::Unnamed(fields) => {
+                let unnamed = expand_elements(std::mem::take(&mut fields.unnamed), true, &vars)?;
+                fields.unnamed = (|input: ParseStream| {
+                    Punctuated::parse_terminated_with(input, Field::parse_unnamed)
+                })
+                .parse2(unnamed)?;
+            }
+            Fields::Unit => {}
+        },
+        Item::Impl(item) => {
+            let items = expand_elements(std::mem::take(&mut item.items), false, &vars)?;
+            item.items = parse_all.parse2(items)?;
+        }
+        item => {
+            return Err(syn::Error::new_spanned(
+                item,
+                "`#[seq_for]` expects an enum, a struct or an impl block",
+            ))
+        }
+    }
+    Ok(item.into_token_stream())
+}
+
+/// A variant, field or associated item that `#[seq_each]` can mark.
+trait Element: ToTokens {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>>;
+    fn name(&mut self) -> Option<&mut Ident>;
+}
+
+impl Element for Variant {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+
+    fn name(&mut self) -> Option<&mut Ident> {
+        Some(&mut self.ident)
+    }
+}
+
+impl Element for Field {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>> {
+        Some(&mut self.attrs)
+    }
+
+    fn name(&mut self) -> Option<&mut Ident> {
+        self.ident.as_mut()
+    }
+}
+
+impl Element for ImplItem {
+    fn attrs(&mut self) -> Option<&mut Vec<Attribute>> {
+        match self {
+            ImplItem::Const(item) => Some(&mut item.attrs),
+            ImplItem::Method(item) => Some(&mut item.attrs),
+            ImplItem::Type(item) => Some(&mut item.attrs),
+            ImplItem::Macro(item) => Some(&mut item.attrs),
+            _ => None,
+        }
+    }
+
+    fn name(&mut self) -> Option<&mut Ident> {
+        match self {
+            ImplItem::Const(item) => Some(&mut item.ident),
+            ImplItem::Method(item) => Some(&mut item.sig.ident),
+            ImplItem::Type(item) => Some(&mut item.ident),
+            _ => None,
+        }
+    }
+}
+
+fn parse_all<T: Parse>(input: ParseStream) -> syn::Result<Vec<T>> {
+    let mut elements = vec![];
+    while !input.is_empty() {
+        elements.push(input.parse()?);
+    }
+    Ok(elements)
+}
+
+/// The tokens of `elements`, with the ones marked `#[seq_each]` repeated and
+/// each one followed by a comma if `comma` is set.
+fn expand_elements<T: Element>(
+    elements: impl IntoIterator<Item = T>,
+    comma: bool,
+    vars: &[LoopVar],
+) -> syn::Result<TokenStream2> {
+    let mut tokens = TokenStream2::new();
+    for mut element in elements {
+        let mut each = None;
+        let mut name = None;
+        if let Some(attrs) = element.attrs() {
+            for attr in std::mem::take(attrs) {
+                let marker = if attr.path.is_ident("seq_each") {
+                    &mut each
+                } else if attr.path.is_ident("seq_name") {
+                    &mut name
+                } else {
+                    attrs.push(attr);
+                    continue;
+                };
+                if marker.is_some() {
+                    return Err(syn::Error::new_spanned(attr, "duplicate attribute"));
+                }
+                *marker = Some(attr);
+            }
+        }
+
+        match (each, name) {
+            (None, None) => {}
+            (None, Some(name)) => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "`#[seq_name]` only applies together with `#[seq_each]`",
+                ))
+            }
+            (Some(each), name) => {
+                let mut body = match name {
+                    Some(name) => rename(&mut element, &name)?,
+                    None => element.to_token_stream(),
+                };
+                if comma {
+                    body.append(Punct::new(',', Spacing::Alone));
+                }
+                tokens.extend(repeat(body, &each, vars)?);
+                continue;
+            }
+        }
+        element.to_tokens(&mut tokens);
+        if comma {
+            tokens.append(Punct::new(',', Spacing::Alone));
+        }
+    }
+    Ok(tokens)
+}
+
+/// The tokens of `element`, with its name replaced by the tokens given in
+/// `#[seq_name(...)]`. Those are not an identifier until they are pasted, so
+/// the name is first set to a placeholder that the tokens then take the place
+/// of.
+fn rename<T: Element>(element: &mut T, attr: &Attribute) -> syn::Result<TokenStream2> {
+    let replacement: TokenStream2 = attr.parse_args()?;
+    let Some(name) = element.name() else {
+        return Err(syn::Error::new_spanned(
+            attr,
+            "`#[seq_name]` needs an item with a name",
+        ));
+    };
+    let placeholder = Ident::new("__seq_name", name.span());
+    *name = placeholder.clone();
+
+    let mut replacement = Some(replacement);
+    Ok(element
+        .to_token_stream()
+        .into_iter()
+        .flat_map(|t| match &t {
+            TokenTree::Ident(ident) if *ident == placeholder && replacement.is_some() => {
+                replacement.take().unwrap()
+            }
+            _ => t.into(),
+        })
+        .collect())
+}
+
+/// Repeats `body` like the repeat section `#(body)*`, or `#(body)N*` and
+/// `#(body)I in 0..4*` for `#[seq_each(N)]` and `#[seq_each(I in 0..4)]`.
+fn repeat(body: TokenStream2, each: &Attribute, vars: &[LoopVar]) -> syn::Result<TokenStream2> {
+    let header = if each.tokens.is_empty() {
+        TokenStream2::new()
+    } else {
+        each.parse_args::<TokenStream2>()?
+    };
+    if !header.is_empty() {
+        match syn::parse2::<Ident>(header.clone()) {
+            Ok(ident) if !vars.iter().any(|var| var.binds(&ident)) => {
+                return Err(syn::Error::new_spanned(ident, "unknown loop variable"))
+            }
+            Ok(_) => {}
+            Err(_) => {
+                syn::parse2::<SeqVar>(header.clone())?;
+            }
+        }
+    }
+
+    let mut section = TokenStream2::new();
+    section.append(Punct::new('#', Spacing::Alone));
+    section.append(Group::new(Delimiter::Parenthesis, body));
+    section.extend(header);
+    section.append(Punct::new('*', Spacing::Alone));
+
+    let (template, whole_body) = Template::compile(section, vars)?;
+    if whole_body {
+        return Err(syn::Error::new_spanned(
+            each,
+            "this item uses a loop variable that `#[seq_each]` does not repeat it over",
+        ));
+    }
+    let vars: Vec<&LoopVar> = vars.iter().collect();
+    let mut expanded = TokenStream2::new();
+    template.instantiate(&vars, &mut vec![], &mut expanded)?;
+    Ok(expanded)
+}
@@ -0,0 +1,388 @@
+use crate::int::Int;
+use crate::{bound, suffix_bounds, Bindings, SeqInt, Value};
+use proc_macro2::{Ident, Punct, Spacing, Span, TokenStream as TokenStream2};
+use quote::TokenStreamExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{parenthesized, token, LitInt, Token};
+
+/// An expression over loop variables, evaluated at expansion time. Integer
+/// expressions are used in `#[N * 8]`, `~(N + 1)` and the bounds of a range,
+/// and conditions like `N % 2 == 0 && N > 1` in `#[seq_if(...)]`.
+#[derive(Debug)]
+pub(crate) enum Expr {
+    Lit(LitInt),
+    Var(Ident),
+    Neg(Span, Box<Expr>),
+    Not(Span, Box<Expr>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum BinOp {
+    Mul(Span),
+    Div(Span),
+    Rem(Span),
+    Add(Span),
+    Sub(Span),
+    Shl(Span),
+    Shr(Span),
+    BitAnd(Span),
+    BitXor(Span),
+    BitOr(Span),
+    Eq(Span),
+    Ne(Span),
+    Lt(Span),
+    Le(Span),
+    Gt(Span),
+    Ge(Span),
+    And(Span),
+    Or(Span),
+}
+
+impl BinOp {
+    fn peek(input: ParseStream) -> Option<Self> {
+        let span = input.span();
+        Some(if input.peek(Token![<<]) {
+            BinOp::Shl(span)
+        } else if input.peek(Token![>>]) {
+            BinOp::Shr(span)
+        } else if input.peek(Token![==]) {
+            BinOp::Eq(span)
+        } else if input.peek(Token![!=]) {
+            BinOp::Ne(span)
+        } else if input.peek(Token![<=]) {
+            BinOp::Le(span)
+        } else if input.peek(Token![>=]) {
+            BinOp::Ge(span)
+        } else if input.peek(Token![<]) {
+            BinOp::Lt(span)
+        } else if input.peek(Token![>]) {
+            BinOp::Gt(span)
+        } else if input.peek(Token![&&]) {
+            BinOp::And(span)
+        } else if input.peek(Token![||]) {
+            BinOp::Or(span)
+        } else if input.peek(Token![&]) {
+            BinOp::BitAnd(span)
+        } else if input.peek(Token![^]) {
+            BinOp::BitXor(span)
+        } else if input.peek(Token![|]) {
+            BinOp::BitOr(span)
+        } else if input.peek(Token![*]) {
+            BinOp::Mul(span)
+        } else if input.peek(Token![/]) {
+            BinOp::Div(span)
+        } else if input.peek(Token![%]) {
+            BinOp::Rem(span)
+        } else if input.peek(Token![+]) {
+            BinOp::Add(span)
+        } else if input.peek(Token![-]) {
+            BinOp::Sub(span)
+        } else {
+            return None;
+        })
+    }
+
+    fn consume(self, input: ParseStream) -> syn::Result<()> {
+        match self {
+            BinOp::Shl(_) => {
+                input.parse::<Token![<<]>()?;
+            }
+            BinOp::Shr(_) => {
+                input.parse::<Token![>>]>()?;
+            }
+            BinOp::Mul(_) => {
+                input.parse::<Token![*]>()?;
+            }
+            BinOp::Div(_) => {
+                input.parse::<Token![/]>()?;
+            }
+            BinOp::Rem(_) => {
+                input.parse::<Token![%]>()?;
+            }
+            BinOp::Add(_) => {
+                input.parse::<Token![+]>()?;
+            }
+            BinOp::Sub(_) => {
+                input.parse::<Token![-]>()?;
+            }
+            BinOp::BitAnd(_) => {
+                input.parse::<Token![&]>()?;
+            }
+            BinOp::BitXor(_) => {
+                input.parse::<Token![^]>()?;
+            }
+            BinOp::BitOr(_) => {
+                input.parse::<Token![|]>()?;
+            }
+            BinOp::Eq(_) => {
+                input.parse::<Token![==]>()?;
+            }
+            BinOp::Ne(_) => {
+                input.parse::<Token![!=]>()?;
+            }
+            BinOp::Lt(_) => {
+                input.parse::<Token![<]>()?;
+            }
+            BinOp::Le(_) => {
+                input.parse::<Token![<=]>()?;
+            }
+            BinOp::Gt(_) => {
+                input.parse::<Token![>]>()?;
+            }
+            BinOp::Ge(_) => {
+                input.parse::<Token![>=]>()?;
+            }
+            BinOp::And(_) => {
+                input.parse::<Token![&&]>()?;
+            }
+            BinOp::Or(_) => {
+                input.parse::<Token![||]>()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Same relative precedence as in Rust.
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Or(_) => 1,
+            BinOp::And(_) => 2,
+            BinOp::Eq(_)
+            | BinOp::Ne(_)
+            | BinOp::Lt(_)
+            | BinOp::Le(_)
+            | BinOp::Gt(_)
+            | BinOp::Ge(_) => 3,
+            BinOp::BitOr(_) => 4,
+            BinOp::BitXor(_) => 5,
+            BinOp::BitAnd(_) => 6,
+            BinOp::Shl(_) | BinOp::Shr(_) => 7,
+            BinOp::Add(_) | BinOp::Sub(_) => 8,
+            BinOp::Mul(_) | BinOp::Div(_) | BinOp::Rem(_) => 9,
+        }
+    }
+
+    fn span(self) -> Span {
+        match self {
+            BinOp::Mul(span)
+            | BinOp::Div(span)
+            | BinOp::Rem(span)
+            | BinOp::Add(span)
+            | BinOp::Sub(span)
+            | BinOp::Shl(span)
+            | BinOp::Shr(span)
+            | BinOp::BitAnd(span)
+            | BinOp::BitXor(span)
+            | BinOp::BitOr(span)
+            | BinOp::Eq(span)
+            | BinOp::Ne(span)
+            | BinOp::Lt(span)
+            | BinOp::Le(span)
+            | BinOp::Gt(span)
+            | BinOp::Ge(span)
+            | BinOp::And(span)
+            | BinOp::Or(span) => span,
+        }
+    }
+
+    fn compare(self, lhs: Int, rhs: Int) -> Option<bool> {
+        Some(match self {
+            BinOp::Eq(_) => lhs == rhs,
+            BinOp::Ne(_) => lhs != rhs,
+            BinOp::Lt(_) => lhs < rhs,
+            BinOp::Le(_) => lhs <= rhs,
+            BinOp::Gt(_) => lhs > rhs,
+            BinOp::Ge(_) => lhs >= rhs,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, lhs: Int, rhs: Int) -> syn::Result<Int> {
+        let (result, span, what) = match self {
+            BinOp::Mul(span) => (lhs.checked_mul(rhs), span, "overflow"),
+            BinOp::Div(span) => (lhs.checked_div(rhs), span, "division by zero"),
+            BinOp::Rem(span) => (lhs.checked_rem(rhs), span, "division by zero"),
+            BinOp::Add(span) => (lhs.checked_add(rhs), span, "overflow"),
+            BinOp::Sub(span) => (lhs.checked_sub(rhs), span, "overflow"),
+            BinOp::Shl(span) => (lhs.checked_shl(rhs), span, "shift out of range"),
+            BinOp::Shr(span) => (lhs.checked_shr(rhs), span, "shift out of range"),
+            BinOp::BitAnd(span) => (lhs.checked_bitand(rhs), span, "overflow"),
+            BinOp::BitXor(span) => (lhs.checked_bitxor(rhs), span, "overflow"),
+            BinOp::BitOr(span) => (lhs.checked_bitor(rhs), span, "overflow"),
+            _ => {
+                return Err(syn::Error::new(
+                    self.span(),
+                    "expected an integer expression, found a condition",
+                ))
+            }
+        };
+        result.ok_or_else(|| syn::Error::new(span, format!("{} in `{} ? {}`", what, lhs, rhs)))
+    }
+}
+
+impl Expr {
+    /// Parses a literal, a loop variable, a negation or a parenthesized
+    /// expression, but no binary operator at the top level. This is what
+    /// range bounds use, so that in `0..N*` the `*` ends the section.
+    pub(crate) fn parse_operand(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![-]) {
+            let minus: Token![-] = input.parse()?;
+            return Ok(Expr::Neg(minus.span, Box::new(Expr::parse_operand(input)?)));
+        }
+        if input.peek(Token![!]) {
+            let not: Token![!] = input.parse()?;
+            return Ok(Expr::Not(not.span, Box::new(Expr::parse_operand(input)?)));
+        }
+        if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            return content.parse();
+        }
+        if input.peek(LitInt) {
+            return Ok(Expr::Lit(input.parse()?));
+        }
+        Ok(Expr::Var(input.parse()?))
+    }
+
+    fn parse_binary(input: ParseStream, min_precedence: u8) -> syn::Result<Self> {
+        let mut lhs = Expr::parse_operand(input)?;
+        while let Some(op) = BinOp::peek(input) {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            op.consume(input)?;
+            let rhs = Expr::parse_binary(input, op.precedence() + 1)?;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    pub(crate) fn span(&self) -> Span {
+        match self {
+            Expr::Lit(lit) => lit.span(),
+            Expr::Var(ident) => ident.span(),
+            Expr::Neg(span, _) | Expr::Not(span, _) => *span,
+            Expr::Binary(lhs, _, _) => lhs.span(),
+        }
+    }
+
+    fn end_span(&self) -> Span {
+        match self {
+            Expr::Lit(lit) => lit.span(),
+            Expr::Var(ident) => ident.span(),
+            Expr::Neg(_, expr) | Expr::Not(_, expr) | Expr::Binary(_, _, expr) => expr.end_span(),
+        }
+    }
+
+    /// The identifiers this expression uses as variables.
+    pub(crate) fn vars(&self) -> Vec<&Ident> {
+        match self {
+            Expr::Lit(_) => vec![],
+            Expr::Var(ident) => vec![ident],
+            Expr::Neg(_, expr) | Expr::Not(_, expr) => expr.vars(),
+            Expr::Binary(lhs, _, rhs) => {
+                let mut vars = lhs.vars();
+                vars.extend(rhs.vars());
+                vars
+            }
+        }
+    }
+
+    /// Evaluates the expression. The result keeps the suffix of the first
+    /// suffixed literal or variable in it.
+    pub(crate) fn eval(&self, bindings: &Bindings) -> syn::Result<SeqInt> {
+        match self {
+            Expr::Lit(lit) => Ok(SeqInt {
+                value: Int::from(lit.base10_parse::<u128>()?),
+                suffix: lit.suffix().to_string(),
+            }),
+            Expr::Var(ident) => match bound(ident, bindings)? {
+                Value::Int(int) => Ok(int.clone()),
+                Value::Tokens(_) => Err(syn::Error::new_spanned(
+                    ident,
+                    format!("the loop variable `{}` is not an integer", ident),
+                )),
+            },
+            Expr::Neg(_, expr) => {
+                let SeqInt { value, suffix } = expr.eval(bindings)?;
+                self.check(SeqInt {
+                    value: value.neg(),
+                    suffix,
+                })
+            }
+            Expr::Not(span, _) => Err(syn::Error::new(
+                *span,
+                "expected an integer expression, found a condition",
+            )),
+            Expr::Binary(lhs, op, rhs) => {
+                let lhs = lhs.eval(bindings)?;
+                let rhs = rhs.eval(bindings)?;
+                self.check(SeqInt {
+                    value: op.apply(lhs.value, rhs.value)?,
+                    suffix: if lhs.suffix.is_empty() {
+                        rhs.suffix
+                    } else {
+                        lhs.suffix
+                    },
+                })
+            }
+        }
+    }
+
+    /// Fails if `int` does not fit in the type named by its suffix, pointing
+    /// at the whole expression rather than leaving it to the compiler.
+    fn check(&self, int: SeqInt) -> syn::Result<SeqInt> {
+        match suffix_bounds(&int.suffix) {
+            Some((min, max)) if int.value < min || int.value > max => {
+                // Tokens at the first and the last span of the expression, for
+                // an error that covers all of it.
+                let mut tokens = TokenStream2::new();
+                for span in [self.span(), self.end_span()] {
+                    let mut punct = Punct::new('.', Spacing::Alone);
+                    punct.set_span(span);
+                    tokens.append(punct);
+                }
+                Err(syn::Error::new_spanned(
+                    tokens,
+                    format!("{} is out of range for `{}`", int, int.suffix),
+                ))
+            }
+            _ => Ok(int),
+        }
+    }
+
+    /// Evaluates a condition made of comparisons joined by `&&`, `||` and `!`.
+    pub(crate) fn eval_bool(&self, bindings: &Bindings) -> syn::Result<bool> {
+        match self {
+            Expr::Not(_, expr) => Ok(!expr.eval_bool(bindings)?),
+            Expr::Binary(lhs, BinOp::And(_), rhs) => {
+                Ok(lhs.eval_bool(bindings)? && rhs.eval_bool(bindings)?)
+            }
+            Expr::Binary(lhs, BinOp::Or(_), rhs) => {
+                Ok(lhs.eval_bool(bindings)? || rhs.eval_bool(bindings)?)
+            }
+            Expr::Binary(lhs, op, rhs) => {
+                let lhs = lhs.eval(bindings)?;
+                let rhs = rhs.eval(bindings)?;
+                match op.compare(lhs.value, rhs.value) {
+                    Some(result) => Ok(result),
+                    None => Err(syn::Error::new(
+                        op.span(),
+                        "expected a condition like `N == 0`, found an integer expression",
+                    )),
+                }
+            }
+            Expr::Lit(_) | Expr::Var(_) | Expr::Neg(..) => Err(syn::Error::new(
+                self.span(),
+                "expected a condition like `N == 0`, found an integer expression",
+            )),
+        }
+    }
+}
+
+impl Parse for Expr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Expr::parse_binary(input, 0)
+    }
+}
@@ -0,0 +1,180 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// An integer of any of the integer types, from `i128::MIN` to `u128::MAX`,
+/// kept as a sign and a magnitude. Operations fail with `None` when the
+/// result falls outside of `-u128::MAX..=u128::MAX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Int {
+    negative: bool,
+    magnitude: u128,
+}
+
+impl Int {
+    pub(crate) const MIN: Int = Int {
+        negative: true,
+        magnitude: u128::MAX,
+    };
+    pub(crate) const MAX: Int = Int {
+        negative: false,
+        magnitude: u128::MAX,
+    };
+
+    pub(crate) fn new(negative: bool, magnitude: u128) -> Self {
+        Int {
+            negative: negative && magnitude != 0,
+            magnitude,
+        }
+    }
+
+    pub(crate) fn is_negative(self) -> bool {
+        self.negative
+    }
+
+    pub(crate) fn unsigned_abs(self) -> u128 {
+        self.magnitude
+    }
+
+    /// The distance between `self` and `other`, or `None` if it does not fit
+    /// in a `u128`.
+    pub(crate) fn abs_diff(self, other: Int) -> Option<u128> {
+        if self.negative == other.negative {
+            Some(self.magnitude.abs_diff(other.magnitude))
+        } else {
+            self.magnitude.checked_add(other.magnitude)
+        }
+    }
+
+    pub(crate) fn neg(self) -> Int {
+        Int::new(!self.negative, self.magnitude)
+    }
+
+    pub(crate) fn checked_add(self, rhs: Int) -> Option<Int> {
+        if self.negative == rhs.negative {
+            let magnitude = self.magnitude.checked_add(rhs.magnitude)?;
+            return Some(Int::new(self.negative, magnitude));
+        }
+        Some(match self.magnitude.cmp(&rhs.magnitude) {
+            Ordering::Less => Int::new(rhs.negative, rhs.magnitude - self.magnitude),
+            _ => Int::new(self.negative, self.magnitude - rhs.magnitude),
+        })
+    }
+
+    pub(crate) fn checked_sub(self, rhs: Int) -> Option<Int> {
+        self.checked_add(rhs.neg())
+    }
+
+    pub(crate) fn checked_mul(self, rhs: Int) -> Option<Int> {
+        let magnitude = self.magnitude.checked_mul(rhs.magnitude)?;
+        Some(Int::new(self.negative != rhs.negative, magnitude))
+    }
+
+    /// Division rounding towards zero, like the integer types.
+    pub(crate) fn checked_div(self, rhs: Int) -> Option<Int> {
+        let magnitude = self.magnitude.checked_div(rhs.magnitude)?;
+        Some(Int::new(self.negative != rhs.negative, magnitude))
+    }
+
+    /// The remainder of `checked_div`, with the sign of `self`.
+    pub(crate) fn checked_rem(self, rhs: Int) -> Option<Int> {
+        let magnitude = self.magnitude.checked_rem(rhs.magnitude)?;
+        Some(Int::new(self.negative, magnitude))
+    }
+
+    pub(crate) fn checked_shl(self, rhs: Int) -> Option<Int> {
+        let factor = 1u128.checked_shl(rhs.shift()?)?;
+        self.checked_mul(Int::from(factor))
+    }
+
+    /// An arithmetic shift, rounding negative numbers down like the signed
+    /// integer types.
+    pub(crate) fn checked_shr(self, rhs: Int) -> Option<Int> {
+        let shift = rhs.shift()?;
+        Some(match self.negative {
+            false => Int::from(self.magnitude.checked_shr(shift)?),
+            true => Int::new(true, ((self.magnitude - 1).checked_shr(shift)?) + 1),
+        })
+    }
+
+    pub(crate) fn checked_bitand(self, rhs: Int) -> Option<Int> {
+        self.bitwise(rhs, |a, b| a & b, |a, b| a & b)
+    }
+
+    pub(crate) fn checked_bitxor(self, rhs: Int) -> Option<Int> {
+        self.bitwise(rhs, |a, b| a ^ b, |a, b| a ^ b)
+    }
+
+    pub(crate) fn checked_bitor(self, rhs: Int) -> Option<Int> {
+        self.bitwise(rhs, |a, b| a | b, |a, b| a | b)
+    }
+
+    fn shift(self) -> Option<u32> {
+        match self.negative {
+            false => u32::try_from(self.magnitude).ok(),
+            true => None,
+        }
+    }
+
+    /// Applies a bitwise operation to the two's complement of both numbers,
+    /// which needs 129 bits: a sign bit and 128 lower bits.
+    fn bitwise(
+        self,
+        rhs: Int,
+        sign: impl Fn(bool, bool) -> bool,
+        bits: impl Fn(u128, u128) -> u128,
+    ) -> Option<Int> {
+        let (lhs_sign, lhs_bits) = self.twos_complement();
+        let (rhs_sign, rhs_bits) = rhs.twos_complement();
+        match (sign(lhs_sign, rhs_sign), bits(lhs_bits, rhs_bits)) {
+            (false, bits) => Some(Int::from(bits)),
+            // -2^128, one below `Int::MIN`.
+            (true, 0) => None,
+            (true, bits) => Some(Int::new(true, bits.wrapping_neg())),
+        }
+    }
+
+    fn twos_complement(self) -> (bool, u128) {
+        match self.negative {
+            false => (false, self.magnitude),
+            true => (true, self.magnitude.wrapping_neg()),
+        }
+    }
+}
+
+impl From<u128> for Int {
+    fn from(value: u128) -> Self {
+        Int::new(false, value)
+    }
+}
+
+impl From<i128> for Int {
+    fn from(value: i128) -> Self {
+        Int::new(value < 0, value.unsigned_abs())
+    }
+}
+
+impl Ord for Int {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Int {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Int {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.negative {
+            f.write_str("-")?;
+        }
+        self.magnitude.fmt(f)
+    }
+}
@@ -1,179 +1,1006 @@
 use proc_macro::TokenStream;
-use proc_macro2::Delimiter::Parenthesis;
-use proc_macro2::{Group, Ident, Literal, TokenStream as TokenStream2, TokenTree};
+use proc_macro2::{
+    Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream as TokenStream2, TokenTree,
+};
 use quote::TokenStreamExt;
-use std::ops::Range;
+use std::fmt;
 use syn::parse::{Parse, ParseStream};
-use syn::{braced, parse_macro_input, LitInt, Token};
+use syn::punctuated::Punctuated;
+use syn::{braced, bracketed, parenthesized, parse_macro_input, token, LitInt, Token};
+
+mod attr;
+mod expr;
+mod int;
+mod template;
+
+use expr::Expr;
+use int::Int;
+use template::Template;
 
 #[proc_macro]
 pub fn seq(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as SeqInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    let start = match input.start.base10_parse::<usize>() {
-        Ok(x) => x,
-        Err(x) => return x.into_compile_error().into(),
-    };
-
-    let end = match input.end.base10_parse::<usize>() {
-        Ok(x) => x,
-        Err(x) => return x.into_compile_error().into(),
-    };
+/// Repeats only the parts of an item marked `#[seq_each]`, so that the rest
+/// of it stays ordinary Rust for rustfmt and IDEs:
+///
+/// ```ignore
+/// #[seq_for(N in 0..4)]
+/// pub enum Register {
+///     Status,
+///     #[seq_each]
+///     #[seq_name(Reg~N)]
+///     Reg = 0x10 + N,
+/// }
+/// ```
+///
+/// An attribute macro cannot be named `seq` like the function-like macro, and
+/// the item has to parse as Rust before it is expanded, so `#(...)*` and `~`
+/// cannot appear in it directly. `#[seq_each]` marks a variant, field or
+/// associated item to repeat, like `#(...)*`, and takes the same headers as a
+/// repeat section, as in `#[seq_each(N)]` or `#[seq_each(I in 0..4)]`.
+/// `#[seq_name(...)]` gives the name of each copy, pasted like in `seq!`.
+/// Inside the marked item, loop variables are substituted as in `seq!`,
+/// including in string literals and in the tokens of macro invocations,
+/// where `~` can be used.
+#[proc_macro_attribute]
+pub fn seq_for(args: TokenStream, input: TokenStream) -> TokenStream {
+    attr::expand(args.into(), input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    let range = if input.range_inclusive_eq_token.is_some() {
-        start..(end + 1)
+fn expand(input: SeqInput) -> syn::Result<TokenStream2> {
+    let vars = loop_vars(&input.vars)?;
+    let span = input
+        .vars
+        .first()
+        .map_or_else(Span::call_site, |var| var.ident.span());
+    let (template, whole_body) = Template::compile(input.body, &vars)?;
+    let vars: Vec<&LoopVar> = vars.iter().collect();
+    let mut expanded = TokenStream2::new();
+    if whole_body {
+        for mut bindings in product(vars.iter().copied(), span)? {
+            template.instantiate(&vars, &mut bindings, &mut expanded)?;
+        }
     } else {
-        start..end
-    };
+        template.instantiate(&vars, &mut vec![], &mut expanded)?;
+    }
+    Ok(expanded)
+}
 
-    match replace(input.body.clone(), (input.var.clone(), None), range.clone()) {
-        Ok(ts) => ts.into(),
-        Err(_) => {
-            let generated_codes = range.clone().map(|i| {
-                replace(
-                    input.body.clone(),
-                    (input.var.clone(), Some(i)),
-                    range.clone(),
-                )
-                .unwrap()
-            });
+fn loop_vars(header: &Punctuated<SeqVar, Token![,]>) -> syn::Result<Vec<LoopVar>> {
+    let mut vars = Vec::<LoopVar>::new();
+    for var in header {
+        if let Some(ident) = var
+            .names()
+            .find(|ident| vars.iter().any(|var| var.binds(ident)))
+        {
+            return Err(syn::Error::new_spanned(ident, "duplicate loop variable"));
+        }
+        vars.push(var.loop_var(&vec![])?);
+    }
+    Ok(vars)
+}
+
+/// A value of the loop variable, keeping the integer suffix written in the
+/// header so that `0u8..4u8` expands to `0u8`, `1u8`, ...
+#[derive(Debug, Clone)]
+struct SeqInt {
+    value: Int,
+    suffix: String,
+}
+
+impl SeqInt {
+    fn to_tokens(&self, span: Span, tokens: &mut TokenStream2) {
+        let mut literal = match self.suffix.as_str() {
+            "" => Literal::u128_unsuffixed(self.value.unsigned_abs()),
+            suffix => format!("{}{}", self.value.unsigned_abs(), suffix)
+                .parse()
+                .unwrap(),
+        };
+        literal.set_span(span);
+
+        if !self.value.is_negative() {
+            tokens.append(literal);
+            return;
+        }
+
+        // A negative number is two tokens. Keep them in an invisible group so
+        // that the sign stays attached in expressions like `N * 2`.
+        let mut minus = Punct::new('-', Spacing::Alone);
+        minus.set_span(span);
+        let mut stream = TokenStream2::new();
+        stream.append(minus);
+        stream.append(literal);
+        let mut group = Group::new(Delimiter::None, stream);
+        group.set_span(span);
+        tokens.append(group);
+    }
+}
+
+impl fmt::Display for SeqInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+/// A value of a loop variable: an integer from a range, or one item of a
+/// token list like `[u8, u16]`.
+#[derive(Debug, Clone)]
+enum Value {
+    Int(SeqInt),
+    Tokens(TokenStream2),
+}
 
-            let mut acc = TokenStream2::new();
-            for generated_code in generated_codes {
-                acc.extend(generated_code);
+impl Value {
+    fn to_tokens(&self, span: Span, tokens: &mut TokenStream2) {
+        match self {
+            Value::Int(int) => int.to_tokens(span, tokens),
+            Value::Tokens(item) if item.clone().into_iter().nth(1).is_none() => {
+                tokens.extend(respan(item, span))
             }
-            acc.into()
+            // Keep items like `a + b` together when they are used as an
+            // operand.
+            Value::Tokens(item) => {
+                let mut group = Group::new(Delimiter::None, respan(item, span));
+                group.set_span(span);
+                tokens.append(group);
+            }
+        }
+    }
+
+    /// The text this value contributes to a pasted identifier.
+    fn paste(&self, spec: Option<&PasteSpec>) -> Result<String, String> {
+        let text = match self {
+            Value::Int(int) if int.value.is_negative() => None,
+            Value::Int(int) => Some(int.value.to_string()),
+            Value::Tokens(tokens) => {
+                let mut iter = tokens.clone().into_iter();
+                match (iter.next(), iter.next()) {
+                    (Some(TokenTree::Ident(ident)), None) => Some(ident.to_string()),
+                    (Some(TokenTree::Literal(lit)), None) => Some(lit.to_string()),
+                    _ => None,
+                }
+            }
+        };
+        let text = match text {
+            Some(text) if text.chars().all(|c| c.is_alphanumeric() || c == '_') => text,
+            _ => return Err(format!("cannot paste `{}` into an identifier", self)),
+        };
+
+        match (spec, self) {
+            (None, _) => Ok(text),
+            (Some(PasteSpec::Case(case)), _) => Ok(case.apply(&text)),
+            (Some(PasteSpec::Int { width, radix }), Value::Int(int)) => {
+                Ok(radix.format(int.value.unsigned_abs(), *width))
+            }
+            (Some(PasteSpec::Int { .. }), Value::Tokens(_)) => Err(format!(
+                "`{}` is not an integer and cannot be formatted as one",
+                self
+            )),
         }
     }
 }
 
-#[derive(Debug)]
-struct UnmetSpecificArea;
-
-fn replace(
-    ts: TokenStream2,
-    var: (Ident, Option<usize>),
-    range: Range<usize>,
-) -> Result<TokenStream2, UnmetSpecificArea> {
-    let mut iter = ts.into_iter().peekable();
-    let mut ts = TokenStream2::new();
-
-    while let Some(t0) = iter.next() {
-        match &t0 {
+/// `tokens` with every span set to `span`, so that errors about an item of a
+/// token list point at where the loop variable is used.
+fn respan(tokens: &TokenStream2, span: Span) -> TokenStream2 {
+    tokens
+        .clone()
+        .into_iter()
+        .map(|t| match t {
             TokenTree::Group(g) => {
-                let mut new_group = Group::new(
-                    g.delimiter(),
-                    replace(g.stream(), var.clone(), range.clone())?,
-                );
-                new_group.set_span(g.span());
-                ts.append(TokenTree::Group(new_group));
-                continue;
-            }
-            TokenTree::Ident(ident) if ident.to_string() == var.0.to_string() => {
-                ts.append(TokenTree::Literal(Literal::usize_unsuffixed(
-                    var.1.ok_or(UnmetSpecificArea)?,
-                )));
-                continue;
-            }
-            TokenTree::Ident(ident) => {
-                if let Some(tilde) = iter.next_if(|t1| match t1 {
-                    TokenTree::Punct(punct) if punct.as_char() == '~' => true,
-                    _ => false,
-                }) {
-                    match iter.next() {
-                        Some(TokenTree::Ident(v)) if v.to_string() == var.0.to_string() => {
-                            let new_ident = Ident::new(
-                                format!("{}{}", ident.to_string(), var.1.ok_or(UnmetSpecificArea)?)
-                                    .as_str(),
-                                ident.span(),
-                            );
-                            ts.append(new_ident);
-                            continue;
-                        }
-                        _ => {
-                            return Ok(syn::Error::new_spanned(tilde, "invalid usage of `~`")
-                                .into_compile_error())
-                        }
+                let mut group = Group::new(g.delimiter(), respan(&g.stream(), span));
+                group.set_span(span);
+                TokenTree::Group(group)
+            }
+            mut t => {
+                t.set_span(span);
+                t
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(int) => int.fmt(f),
+            Value::Tokens(tokens) => tokens.fmt(f),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct LoopVar {
+    ident: Ident,
+    /// The `I` of `(I, T) in [...]`, bound to the position of each value.
+    index: Option<Ident>,
+    values: Vec<Value>,
+}
+
+impl LoopVar {
+    fn binds(&self, ident: &Ident) -> bool {
+        self.ident == *ident || self.index.as_ref() == Some(ident)
+    }
+}
+
+/// The values of the loop variables in the current iteration. Inner repeat
+/// sections push their variables last, so lookups search from the end.
+type Bindings = Vec<(Ident, Value)>;
+
+/// The most times `seq!` repeats a body, or one of its repeat sections. More
+/// than this is almost certainly a mistake, and would only make the compiler
+/// run out of memory.
+const MAX_ITERATIONS: usize = 65536;
+
+/// Every combination of the values of `vars`, with the first variable
+/// changing slowest. Fails if there are more than `MAX_ITERATIONS`, with the
+/// error at `span`.
+fn product<'a>(
+    vars: impl IntoIterator<Item = &'a LoopVar>,
+    span: Span,
+) -> syn::Result<Vec<Bindings>> {
+    let vars: Vec<_> = vars.into_iter().collect();
+    let count = vars
+        .iter()
+        .try_fold(1usize, |count, var| count.checked_mul(var.values.len()));
+    if count.map_or(true, |count| count > MAX_ITERATIONS) {
+        return Err(syn::Error::new(
+            span,
+            format!("too many iterations, the limit is {}", MAX_ITERATIONS),
+        ));
+    }
+
+    Ok(vars.into_iter().fold(vec![vec![]], |acc, var| {
+        acc.into_iter()
+            .flat_map(|bindings| {
+                var.values.iter().enumerate().map(move |(i, value)| {
+                    let mut bindings = bindings.clone();
+                    if let Some(index) = &var.index {
+                        let position = SeqInt {
+                            value: Int::from(i as u128),
+                            suffix: String::new(),
+                        };
+                        bindings.push((index.clone(), Value::Int(position)));
                     }
-                }
+                    bindings.push((var.ident.clone(), value.clone()));
+                    bindings
+                })
+            })
+            .collect()
+    }))
+}
 
-                ts.append(t0);
-                continue;
-            }
-            TokenTree::Punct(punct) if punct.as_char() == '#' => {
-                if let Some(TokenTree::Group(paren_group)) = iter.next_if(|t1| match t1 {
-                    TokenTree::Group(g) if g.delimiter() == Parenthesis => true,
-                    _ => false,
-                }) {
-                    if iter
-                        .next_if(|t2| match t2 {
-                            TokenTree::Punct(punct) if punct.as_char() == '*' => true,
-                            _ => false,
-                        })
-                        .is_some()
-                    {
-                        let specific_area = paren_group.stream();
-                        for i in range.clone() {
-                            let interpolated_part = replace(
-                                specific_area.clone(),
-                                (var.0.clone(), Some(i)),
-                                range.clone(),
-                            );
-                            ts.append_all(interpolated_part);
-                        }
-                        continue;
+/// The value of the loop variable `ident` in the current iteration.
+fn bound<'a>(ident: &Ident, bindings: &'a Bindings) -> syn::Result<&'a Value> {
+    match bindings.iter().rev().find(|(name, _)| name == ident) {
+        Some((_, value)) => Ok(value),
+        None => Err(syn::Error::new_spanned(
+            ident,
+            format!("cannot find loop variable `{}` in this scope", ident),
+        )),
+    }
+}
+
+fn is_punct(t: &TokenTree, c: char) -> bool {
+    matches!(t, TokenTree::Punct(punct) if punct.as_char() == c)
+}
+
+/// The format of a loop variable in a pasted identifier, written after it
+/// in braces like `Reg~{N:02X}` or `~{T:snake}`.
+enum PasteSpec {
+    /// Zero-padded to `width` digits in the given radix, e.g. `:02`, `:x`.
+    Int {
+        width: usize,
+        radix: Radix,
+    },
+    Case(Case),
+}
+
+#[derive(Clone, Copy)]
+enum Radix {
+    Decimal,
+    LowerHex,
+    UpperHex,
+    Binary,
+    Octal,
+}
+
+#[derive(Clone, Copy)]
+enum Case {
+    Lower,
+    Upper,
+    Snake,
+    UpperSnake,
+    Camel,
+    LowerCamel,
+}
+
+impl PasteSpec {
+    /// Parses the `:spec` at the end of a `{N:spec}` placeholder.
+    fn parse(tokens: TokenStream2) -> Option<Self> {
+        let mut iter = tokens.into_iter();
+        match iter.next()? {
+            TokenTree::Punct(colon)
+                if colon.as_char() == ':' && colon.spacing() == Spacing::Alone => {}
+            _ => return None,
+        }
+        let spec = match iter.next()? {
+            TokenTree::Ident(ident) => match ident.to_string().as_str() {
+                "lower" => PasteSpec::Case(Case::Lower),
+                "upper" => PasteSpec::Case(Case::Upper),
+                "snake" => PasteSpec::Case(Case::Snake),
+                "upper_snake" => PasteSpec::Case(Case::UpperSnake),
+                "camel" => PasteSpec::Case(Case::Camel),
+                "lower_camel" => PasteSpec::Case(Case::LowerCamel),
+                radix => PasteSpec::Int {
+                    width: 0,
+                    radix: Radix::from_suffix(radix)?,
+                },
+            },
+            TokenTree::Literal(literal) => match syn::Lit::new(literal) {
+                syn::Lit::Int(literal) => PasteSpec::Int {
+                    width: literal.base10_parse().ok()?,
+                    radix: Radix::from_suffix(literal.suffix())?,
+                },
+                _ => return None,
+            },
+            _ => return None,
+        };
+        match iter.next() {
+            Some(_) => None,
+            None => Some(spec),
+        }
+    }
+
+    /// Parses the text after the `:` of a placeholder in a string literal.
+    fn from_str(spec: &str) -> Option<Self> {
+        PasteSpec::parse(format!(":{}", spec).parse().ok()?)
+    }
+}
+
+impl Radix {
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        Some(match suffix {
+            "" => Radix::Decimal,
+            "x" => Radix::LowerHex,
+            "X" => Radix::UpperHex,
+            "b" => Radix::Binary,
+            "o" => Radix::Octal,
+            _ => return None,
+        })
+    }
+
+    fn format(self, value: u128, width: usize) -> String {
+        match self {
+            Radix::Decimal => format!("{:0width$}", value, width = width),
+            Radix::LowerHex => format!("{:0width$x}", value, width = width),
+            Radix::UpperHex => format!("{:0width$X}", value, width = width),
+            Radix::Binary => format!("{:0width$b}", value, width = width),
+            Radix::Octal => format!("{:0width$o}", value, width = width),
+        }
+    }
+}
+
+impl Case {
+    fn apply(self, text: &str) -> String {
+        let words = split_words(text);
+        let capitalize = |word: &String| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(c) => c
+                    .to_uppercase()
+                    .chain(chars.flat_map(char::to_lowercase))
+                    .collect(),
+                None => String::new(),
+            }
+        };
+
+        match self {
+            Case::Lower => text.to_lowercase(),
+            Case::Upper => text.to_uppercase(),
+            Case::Snake => words
+                .iter()
+                .map(|w| w.to_lowercase())
+                .collect::<Vec<_>>()
+                .join("_"),
+            Case::UpperSnake => words
+                .iter()
+                .map(|w| w.to_uppercase())
+                .collect::<Vec<_>>()
+                .join("_"),
+            Case::Camel => words.iter().map(capitalize).collect(),
+            Case::LowerCamel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| {
+                    if i == 0 {
+                        w.to_lowercase()
                     } else {
-                        let mut new_group = Group::new(
-                            paren_group.delimiter(),
-                            replace(paren_group.stream(), var.clone(), range.clone())?,
-                        );
-                        new_group.set_span(paren_group.span());
-                        ts.append(paren_group);
-                        continue;
+                        capitalize(w)
                     }
-                }
+                })
+                .collect(),
+        }
+    }
+}
 
-                ts.append(t0);
-                continue;
-            }
-            _ => {
-                ts.append(t0);
-                continue;
+/// Splits `snake_case`, `CamelCase` and `HTTPServer` style names into words.
+fn split_words(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut words = vec![];
+    let mut word = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
             }
-        };
+            continue;
+        }
+        let boundary = c.is_uppercase()
+            && i > 0
+            && (chars[i - 1].is_lowercase()
+                || chars[i - 1].is_ascii_digit()
+                || (chars[i - 1].is_uppercase()
+                    && chars.get(i + 1).map_or(false, |next| next.is_lowercase())));
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
     }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
 
-    Ok(ts)
+/// Whether `word` is a Rust keyword. A keyword in front of `~` is not pasted
+/// onto, so that `fn ~{T:snake}()` names a function.
+fn is_keyword(word: &str) -> bool {
+    matches!(
+        word,
+        "as" | "async"
+            | "await"
+            | "break"
+            | "const"
+            | "continue"
+            | "crate"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "self"
+            | "Self"
+            | "static"
+            | "struct"
+            | "super"
+            | "trait"
+            | "true"
+            | "type"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+            | "abstract"
+            | "become"
+            | "box"
+            | "do"
+            | "final"
+            | "macro"
+            | "override"
+            | "priv"
+            | "try"
+            | "typeof"
+            | "unsized"
+            | "virtual"
+            | "yield"
+    )
 }
 
 #[derive(Debug)]
 struct SeqInput {
-    var: Ident,
+    vars: Punctuated<SeqVar, Token![,]>,
+    #[allow(dead_code)]
+    brace_token: syn::token::Brace,
+    body: TokenStream2,
+}
+
+/// One `N in 0..8`, `T in [u8, u16]` or `(I, T) in [u8, u16]` clause of the
+/// header.
+#[derive(Debug)]
+struct SeqVar {
+    index: Option<Ident>,
+    ident: Ident,
     #[allow(dead_code)]
     in_token: Token![in],
-    start: LitInt,
+    source: SeqSource,
+}
+
+#[derive(Debug)]
+enum SeqSource {
+    Range(SeqRange),
+    List(Vec<TokenStream2>),
+}
+
+impl SeqVar {
+    fn names(&self) -> impl Iterator<Item = &Ident> {
+        self.index.iter().chain([&self.ident])
+    }
+
+    fn bound_vars(&self) -> impl Iterator<Item = &Ident> {
+        match &self.source {
+            SeqSource::Range(range) => Some(range.bound_vars()),
+            SeqSource::List(_) => None,
+        }
+        .into_iter()
+        .flatten()
+    }
+
+    fn loop_var(&self, bindings: &Bindings) -> syn::Result<LoopVar> {
+        let values = match &self.source {
+            SeqSource::Range(range) => range
+                .values(bindings)?
+                .into_iter()
+                .map(Value::Int)
+                .collect(),
+            SeqSource::List(items) => items.iter().cloned().map(Value::Tokens).collect(),
+        };
+        Ok(LoopVar {
+            ident: self.ident.clone(),
+            index: self.index.clone(),
+            values,
+        })
+    }
+}
+
+/// Splits the contents of `[...]` at the commas between items. Commas inside
+/// `<...>` do not split, so that items like `HashMap<K, V>` stay whole.
+fn parse_list_items(input: ParseStream) -> syn::Result<Vec<TokenStream2>> {
+    let mut items = vec![];
+    let mut item = TokenStream2::new();
+    let mut depth = 0usize;
+    let mut previous_joint = None;
+    while !input.is_empty() {
+        let t: TokenTree = input.parse()?;
+        if let TokenTree::Punct(punct) = &t {
+            match (punct.as_char(), previous_joint) {
+                ('<', _) => depth += 1,
+                // `->` and `=>` are not closing brackets.
+                ('>', Some('-' | '=')) => {}
+                ('>', _) => depth = depth.saturating_sub(1),
+                (',', _) if depth == 0 => {
+                    if item.is_empty() {
+                        return Err(syn::Error::new_spanned(
+                            punct,
+                            "expected an item before `,`",
+                        ));
+                    }
+                    items.push(std::mem::take(&mut item));
+                    previous_joint = None;
+                    continue;
+                }
+                _ => {}
+            }
+            previous_joint = (punct.spacing() == Spacing::Joint).then_some(punct.as_char());
+        } else {
+            previous_joint = None;
+        }
+        item.append(t);
+    }
+    if !item.is_empty() {
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// The range of the loop variable, written either as `0..64 step 8 rev` or as
+/// `(0..64).step_by(8).rev()`. The adapters apply in the order they are
+/// written, like the iterator methods they are named after.
+#[derive(Debug)]
+struct SeqRange {
+    start: Expr,
     #[allow(dead_code)]
     range_token: Token![..],
-    #[allow(dead_code)]
     range_inclusive_eq_token: Option<Token![=]>,
-    end: LitInt,
-    #[allow(dead_code)]
-    brace_token: syn::token::Brace,
-    body: TokenStream2,
+    end: Expr,
+    adapters: Vec<RangeAdapter>,
+}
+
+#[derive(Debug)]
+enum RangeAdapter {
+    StepBy(LitInt),
+    Rev,
+}
+
+impl SeqRange {
+    fn values(&self, bindings: &Bindings) -> syn::Result<Vec<SeqInt>> {
+        let start = self.start.eval(bindings)?;
+        let end = self.end.eval(bindings)?;
+
+        let suffix = match (start.suffix.as_str(), end.suffix.as_str()) {
+            ("", suffix) | (suffix, "") => suffix,
+            (start_suffix, end_suffix) if start_suffix == end_suffix => start_suffix,
+            (start_suffix, _) => {
+                return Err(syn::Error::new(
+                    self.end.span(),
+                    format!(
+                        "expected the same suffix as the start of the range, `{}`",
+                        start_suffix
+                    ),
+                ))
+            }
+        };
+        if let Some((min, max)) = suffix_bounds(suffix) {
+            for (bound, value) in [(&self.start, &start), (&self.end, &end)] {
+                if value.value < min || value.value > max {
+                    return Err(syn::Error::new(
+                        bound.span(),
+                        format!("{} is out of range for `{}`", value, suffix),
+                    ));
+                }
+            }
+        } else if !suffix.is_empty() {
+            return Err(syn::Error::new(
+                self.start.span(),
+                format!("`{}` is not an integer suffix", suffix),
+            ));
+        }
+        let suffix = suffix.to_string();
+        let (start, end) = (start.value, end.value);
+
+        // Worked out as a first value, a stride and the index of the last
+        // value instead of by collecting the range, so that a huge range is
+        // reported as too long rather than running out of memory. Counting
+        // from the last index keeps `0..=u128::MAX` within a `u128`.
+        let too_long = || {
+            syn::Error::new(
+                self.start.span(),
+                format!(
+                    "too many values in this range, the limit is {}",
+                    MAX_ITERATIONS
+                ),
+            )
+        };
+        let mut last = match self.range_inclusive_eq_token {
+            Some(_) if start <= end => Some(start.abs_diff(end).ok_or_else(too_long)?),
+            None if start < end => Some(start.abs_diff(end).ok_or_else(too_long)? - 1),
+            _ => None,
+        };
+        let (mut first, mut stride) = (start, Int::from(1u128));
+        for adapter in &self.adapters {
+            match adapter {
+                RangeAdapter::StepBy(step) => match step.base10_parse::<u128>()? {
+                    0 => return Err(syn::Error::new_spanned(step, "the step must not be zero")),
+                    step => {
+                        last = last.map(|last| last / step);
+                        // Past `u128::MAX` there is only one value, so the
+                        // stride no longer matters.
+                        stride = stride.checked_mul(Int::from(step)).unwrap_or(Int::MAX);
+                    }
+                },
+                RangeAdapter::Rev => {
+                    if let Some(last) = last {
+                        first = stride
+                            .checked_mul(Int::from(last))
+                            .and_then(|n| first.checked_add(n))
+                            .ok_or_else(too_long)?;
+                    }
+                    stride = stride.neg();
+                }
+            }
+        }
+        let count = match last {
+            Some(last) if last >= MAX_ITERATIONS as u128 => return Err(too_long()),
+            Some(last) => last + 1,
+            None => 0,
+        };
+
+        (0..count)
+            .map(|i| {
+                let value = stride
+                    .checked_mul(Int::from(i))
+                    .and_then(|n| first.checked_add(n))
+                    .ok_or_else(too_long)?;
+                Ok(SeqInt {
+                    value,
+                    suffix: suffix.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn bound_vars(&self) -> impl Iterator<Item = &Ident> {
+        let mut vars = self.start.vars();
+        vars.extend(self.end.vars());
+        vars.into_iter()
+    }
+}
+
+/// Bounds of the integer type named by a literal suffix, or `None` if the
+/// suffix is not an integer type.
+fn suffix_bounds(suffix: &str) -> Option<(Int, Int)> {
+    let (min, max): (i128, u128) = match suffix {
+        "" => return Some((Int::MIN, Int::MAX)),
+        "i128" => (i128::MIN, i128::MAX as u128),
+        "u128" => (0, u128::MAX),
+        "i8" => (i8::MIN.into(), i8::MAX as u128),
+        "i16" => (i16::MIN.into(), i16::MAX as u128),
+        "i32" => (i32::MIN.into(), i32::MAX as u128),
+        "i64" | "isize" => (i64::MIN.into(), i64::MAX as u128),
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" | "usize" => (0, u64::MAX.into()),
+        _ => return None,
+    };
+    Some((Int::from(min), Int::from(max)))
+}
+
+impl Parse for SeqRange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(token::Paren) && !is_operand(input) {
+            let content;
+            parenthesized!(content in input);
+            let mut range: SeqRange = content.parse()?;
+            if !range.adapters.is_empty() || !content.is_empty() {
+                return Err(content.error("expected `)`"));
+            }
+
+            while input.peek(Token![.]) {
+                input.parse::<Token![.]>()?;
+                let method: Ident = input.parse()?;
+                let args;
+                parenthesized!(args in input);
+                range.adapters.push(match method.to_string().as_str() {
+                    "step_by" => RangeAdapter::StepBy(args.parse()?),
+                    "rev" => RangeAdapter::Rev,
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            method,
+                            "expected `step_by` or `rev`",
+                        ))
+                    }
+                });
+                if !args.is_empty() {
+                    return Err(args.error("unexpected argument"));
+                }
+            }
+            return Ok(range);
+        }
+
+        let mut range = SeqRange {
+            start: Expr::parse_operand(input)?,
+            range_token: input.parse()?,
+            range_inclusive_eq_token: input.parse()?,
+            end: Expr::parse_operand(input)?,
+            adapters: vec![],
+        };
+        while input.peek(syn::Ident) {
+            let keyword: Ident = input.parse()?;
+            range.adapters.push(match keyword.to_string().as_str() {
+                "step" => RangeAdapter::StepBy(input.parse()?),
+                "rev" => RangeAdapter::Rev,
+                _ => return Err(syn::Error::new_spanned(keyword, "expected `step` or `rev`")),
+            });
+        }
+        Ok(range)
+    }
+}
+
+/// Whether the parentheses ahead are an expression like the `(N + 1)` of
+/// `(N + 1)..8`, rather than a range like `(0..8).rev()`.
+fn is_operand(input: ParseStream) -> bool {
+    let fork = input.fork();
+    Expr::parse_operand(&fork).is_ok() && fork.peek(Token![..])
+}
+
+impl Parse for SeqVar {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let (index, ident) = if input.peek(token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            let index = content.parse()?;
+            content.parse::<Token![,]>()?;
+            let ident = content.parse()?;
+            content.parse::<Option<Token![,]>>()?;
+            (Some(index), ident)
+        } else {
+            (None, input.parse()?)
+        };
+        let in_token = input.parse()?;
+
+        let source = if input.peek(token::Bracket) {
+            let content;
+            bracketed!(content in input);
+            SeqSource::List(parse_list_items(&content)?)
+        } else {
+            SeqSource::Range(input.parse()?)
+        };
+
+        Ok(SeqVar {
+            index,
+            ident,
+            in_token,
+            source,
+        })
+    }
 }
 
 impl Parse for SeqInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut vars = Punctuated::new();
+        loop {
+            vars.push_value(input.parse()?);
+            if input.peek(token::Brace) {
+                break;
+            }
+            vars.push_punct(input.parse()?);
+        }
+
         let content;
         Ok(SeqInput {
-            var: input.parse()?,
-            in_token: input.parse()?,
-            start: input.parse()?,
-            range_token: input.parse()?,
-            range_inclusive_eq_token: input.parse()?,
-            end: input.parse()?,
+            vars,
             brace_token: braced!(content in input),
             body: content.parse()?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// A body that the expansion before templates also supports.
+    const TABLE: &str = r#"
+        N in 0..10000 {
+            pub const ENTRY~N: Entry = Entry {
+                index: N,
+                neighbours: [N, N + 1],
+                name: stringify!(ENTRY~N),
+            };
+        }
+    "#;
+
+    /// How `seq!` expanded bodies before they were compiled into templates,
+    /// kept to benchmark against. A trial pass over the body fails at the
+    /// first loop variable outside of a `#(...)*` section, then the body is
+    /// walked and cloned again for every value.
+    mod baseline {
+        use crate::is_punct;
+        use proc_macro2::{Delimiter, Group, Ident, Literal, TokenStream, TokenTree};
+        use quote::TokenStreamExt;
+        use std::ops::Range;
+
+        struct UnmetSpecificArea;
+
+        pub(super) fn expand(body: TokenStream, var: &Ident, range: Range<usize>) -> TokenStream {
+            if let Ok(expanded) = replace(body.clone(), var, None, range.clone()) {
+                return expanded;
+            }
+            let mut expanded = TokenStream::new();
+            for i in range.clone() {
+                if let Ok(tokens) = replace(body.clone(), var, Some(i), range.clone()) {
+                    expanded.extend(tokens);
+                }
+            }
+            expanded
+        }
+
+        fn replace(
+            ts: TokenStream,
+            var: &Ident,
+            value: Option<usize>,
+            range: Range<usize>,
+        ) -> Result<TokenStream, UnmetSpecificArea> {
+            let mut iter = ts.into_iter().peekable();
+            let mut out = TokenStream::new();
+            while let Some(t0) = iter.next() {
+                match &t0 {
+                    TokenTree::Group(g) => {
+                        let stream = replace(g.stream(), var, value, range.clone())?;
+                        let mut group = Group::new(g.delimiter(), stream);
+                        group.set_span(g.span());
+                        out.append(group);
+                    }
+                    TokenTree::Ident(ident) if ident == var => {
+                        out.append(Literal::usize_unsuffixed(value.ok_or(UnmetSpecificArea)?));
+                    }
+                    TokenTree::Ident(ident)
+                        if iter.peek().map_or(false, |t1| is_punct(t1, '~')) =>
+                    {
+                        let pasted = format!("{}{}", ident, value.ok_or(UnmetSpecificArea)?);
+                        iter.next();
+                        iter.next();
+                        out.append(Ident::new(&pasted, ident.span()));
+                    }
+                    TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                        let mut ahead = iter.clone();
+                        match (ahead.next(), ahead.next()) {
+                            (Some(TokenTree::Group(section)), Some(star))
+                                if section.delimiter() == Delimiter::Parenthesis
+                                    && is_punct(&star, '*') =>
+                            {
+                                iter = ahead;
+                                for i in range.clone() {
+                                    out.extend(replace(
+                                        section.stream(),
+                                        var,
+                                        Some(i),
+                                        range.clone(),
+                                    )?);
+                                }
+                            }
+                            _ => out.append(t0.clone()),
+                        }
+                    }
+                    _ => out.append(t0.clone()),
+                }
+            }
+            Ok(out)
+        }
+    }
+
+    /// The body is compiled once, into a template whose size does not depend
+    /// on the number of iterations.
+    #[test]
+    fn template_size() {
+        let input: SeqInput = syn::parse_str(TABLE).unwrap();
+        let vars = loop_vars(&input.vars).unwrap();
+        let (template, whole_body) = Template::compile(input.body, &vars).unwrap();
+        assert!(whole_body);
+        assert_eq!(template.len(), 17);
+
+        let input: SeqInput = syn::parse_str(&TABLE.replace("10000", "1")).unwrap();
+        let vars = loop_vars(&input.vars).unwrap();
+        let (single, _) = Template::compile(input.body, &vars).unwrap();
+        assert_eq!(single.len(), template.len());
+    }
+
+    /// Run with `cargo test -p seq --release -- --ignored --nocapture`.
+    /// Both expansions run in turns and the fastest run of each is reported,
+    /// so that noise from the machine affects both alike. Outside of a
+    /// compiler, proc_macro2 uses its own token types, so this measures the
+    /// work done by the expansion itself rather than the cost of compiler
+    /// tokens.
+    #[test]
+    #[ignore]
+    fn bench_10k_iterations() {
+        let (mut baseline, mut compiled) = (Duration::MAX, Duration::MAX);
+        for _ in 0..5 {
+            let start = Instant::now();
+            let input: SeqInput = syn::parse_str(TABLE).unwrap();
+            let before = baseline::expand(input.body, &input.vars[0].ident, 0..10000);
+            baseline = baseline.min(start.elapsed());
+
+            let start = Instant::now();
+            let after = expand(syn::parse_str(TABLE).unwrap()).unwrap();
+            compiled = compiled.min(start.elapsed());
+
+            assert_eq!(after.to_string(), before.to_string());
+        }
+        println!("before templates:  {:?}", baseline);
+        println!("compiled template: {:?}", compiled);
+    }
+}
@@ -0,0 +1,824 @@
+use crate::expr::Expr;
+use crate::{bound, is_keyword, is_punct, product, Bindings, LoopVar, PasteSpec, SeqVar, Value};
+use proc_macro2::Delimiter::Parenthesis;
+use proc_macro2::{
+    Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream as TokenStream2, TokenTree,
+};
+use quote::TokenStreamExt;
+use std::iter::Peekable;
+
+/// A `seq!` body, parsed once into runs of tokens that are copied as they are
+/// and the places where loop variables are substituted, then instantiated for
+/// every iteration.
+#[derive(Default)]
+pub(crate) struct Template(Vec<Node>);
+
+enum Node {
+    /// Tokens without loop variables in them.
+    Tokens(TokenStream2),
+    /// A delimited group with loop variables inside.
+    Group(Group, Template),
+    /// A loop variable.
+    Var(Ident),
+    /// An identifier or lifetime pasted together with `~`, as in
+    /// `prefix~N~_suffix` or `'a~N`.
+    Paste {
+        span: Span,
+        kind: PasteKind,
+        segments: Vec<Segment>,
+    },
+    /// A string or byte string literal with placeholders, as in `"Reg{N}"`.
+    Literal {
+        literal: Literal,
+        byte: bool,
+        pieces: Vec<Piece>,
+    },
+    /// `#[N * 8]`.
+    Eval(Span, Expr),
+    /// `#[seq_if(...)] { ... }` and the `else` branches after it.
+    Conditional {
+        branches: Vec<(Expr, Template)>,
+        otherwise: Template,
+    },
+    /// `#(...)*` and the other kinds of repeat sections.
+    Repeat {
+        span: Span,
+        kind: SectionKind,
+        separator: Option<Punct>,
+        body: Template,
+    },
+}
+
+enum PasteKind {
+    Ident,
+    /// Starting with a raw identifier, as in `r#type~N`.
+    Raw,
+    /// Starting with a lifetime, as in `'a~N`, keeping its `'`.
+    Lifetime(Punct),
+}
+
+enum Segment {
+    Text(String),
+    /// A loop variable or a `(...)` expression, and the token written for it.
+    Value(TokenTree, Placeholder, Option<PasteSpec>),
+}
+
+enum Piece {
+    Text(String),
+    Value(Placeholder, Option<PasteSpec>),
+}
+
+enum Placeholder {
+    Var(Ident),
+    Expr(Expr),
+}
+
+impl Placeholder {
+    fn value(&self, bindings: &Bindings) -> syn::Result<Value> {
+        match self {
+            Placeholder::Var(ident) => bound(ident, bindings).cloned(),
+            Placeholder::Expr(expr) => expr.eval(bindings).map(Value::Int),
+        }
+    }
+}
+
+enum SectionKind {
+    /// `#(...)*`, repeated over every combination of the loop variables.
+    All,
+    /// `#(...)N*`, repeated over one of the loop variables.
+    Var(usize),
+    /// `#(...)I in 0..N*`, repeated over a range of its own, which may depend
+    /// on the outer loop variables.
+    Local(SeqVar),
+}
+
+/// A loop variable as the compiler sees it: its names, and whether a repeat
+/// section around the tokens being compiled gives it a value.
+#[derive(Clone)]
+struct ScopeVar {
+    names: Vec<Ident>,
+    bound: bool,
+}
+
+impl ScopeVar {
+    fn binds(&self, ident: &Ident) -> bool {
+        self.names.iter().any(|name| name == ident)
+    }
+}
+
+struct Compiler {
+    /// Set when a loop variable is used outside of any repeat section that
+    /// binds it, in which case the whole body is repeated instead.
+    whole_body: bool,
+}
+
+impl Template {
+    /// Compiles a `seq!` body over the loop variables of the header. Also
+    /// returns whether the body has to be repeated as a whole, because it
+    /// uses the loop variables outside of repeat sections.
+    pub(crate) fn compile(body: TokenStream2, vars: &[LoopVar]) -> syn::Result<(Self, bool)> {
+        let scope: Vec<_> = vars
+            .iter()
+            .map(|var| ScopeVar {
+                names: var.index.iter().chain([&var.ident]).cloned().collect(),
+                bound: false,
+            })
+            .collect();
+        let mut compiler = Compiler { whole_body: false };
+        let template = compiler.compile(body, &scope)?;
+        Ok((template, compiler.whole_body))
+    }
+
+    fn push(&mut self, node: Node) {
+        self.0.push(node);
+    }
+
+    fn push_token(&mut self, token: TokenTree) {
+        match self.0.last_mut() {
+            Some(Node::Tokens(tokens)) => tokens.append(token),
+            _ => self.0.push(Node::Tokens(token.into())),
+        }
+    }
+
+    fn is_static(&self) -> bool {
+        self.0.iter().all(|node| matches!(node, Node::Tokens(_)))
+    }
+
+    /// The number of nodes, counting the ones inside groups, conditionals and
+    /// repeat sections.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.0
+            .iter()
+            .map(|node| match node {
+                Node::Group(_, template) | Node::Repeat { body: template, .. } => {
+                    1 + template.len()
+                }
+                Node::Conditional {
+                    branches,
+                    otherwise,
+                } => {
+                    let branches: usize = branches.iter().map(|(_, body)| body.len()).sum();
+                    1 + branches + otherwise.len()
+                }
+                _ => 1,
+            })
+            .sum()
+    }
+
+    /// Appends the tokens for one iteration to `out`. The `vars` are the loop
+    /// variables in scope, and `bindings` their values.
+    pub(crate) fn instantiate(
+        &self,
+        vars: &[&LoopVar],
+        bindings: &mut Bindings,
+        out: &mut TokenStream2,
+    ) -> syn::Result<()> {
+        for node in &self.0 {
+            match node {
+                Node::Tokens(tokens) => out.extend(tokens.clone()),
+                Node::Group(group, template) => {
+                    let mut stream = TokenStream2::new();
+                    template.instantiate(vars, bindings, &mut stream)?;
+                    let mut new_group = Group::new(group.delimiter(), stream);
+                    new_group.set_span(group.span());
+                    out.append(new_group);
+                }
+                Node::Var(ident) => bound(ident, bindings)?.to_tokens(ident.span(), out),
+                Node::Paste {
+                    span,
+                    kind,
+                    segments,
+                } => {
+                    let mut pasted = String::new();
+                    for segment in segments {
+                        match segment {
+                            Segment::Text(text) => pasted.push_str(text),
+                            Segment::Value(token, placeholder, spec) => {
+                                match placeholder.value(bindings)?.paste(spec.as_ref()) {
+                                    Ok(text) => pasted.push_str(&text),
+                                    Err(message) => {
+                                        return Err(syn::Error::new_spanned(token, message))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    let error = |message: String| Err(syn::Error::new(*span, message));
+                    match kind {
+                        PasteKind::Ident if !is_ident(&pasted) => {
+                            return error(format!("`{}` is not a valid identifier", pasted));
+                        }
+                        PasteKind::Ident if is_keyword(&pasted) => {
+                            return error(format!(
+                                "`{}` is a keyword and cannot be pasted as an identifier",
+                                pasted
+                            ));
+                        }
+                        PasteKind::Ident => out.append(Ident::new(&pasted, *span)),
+                        PasteKind::Raw if !is_ident(&pasted) => {
+                            return error(format!("`r#{}` is not a valid identifier", pasted));
+                        }
+                        PasteKind::Raw
+                            if matches!(&*pasted, "crate" | "self" | "Self" | "super") =>
+                        {
+                            return error(format!("`{}` cannot be a raw identifier", pasted));
+                        }
+                        PasteKind::Raw => out.append(Ident::new_raw(&pasted, *span)),
+                        PasteKind::Lifetime(_) if !is_ident(&pasted) => {
+                            return error(format!("`'{}` is not a valid lifetime", pasted));
+                        }
+                        PasteKind::Lifetime(_) if pasted != "static" && is_keyword(&pasted) => {
+                            return error(format!(
+                                "`'{}` is a keyword, which cannot be a lifetime",
+                                pasted
+                            ));
+                        }
+                        PasteKind::Lifetime(apostrophe) => {
+                            let mut apostrophe = apostrophe.clone();
+                            apostrophe.set_span(*span);
+                            out.append(apostrophe);
+                            out.append(Ident::new(&pasted, *span));
+                        }
+                    }
+                }
+                Node::Literal {
+                    literal,
+                    byte,
+                    pieces,
+                } => {
+                    let error = |message: &dyn std::fmt::Display| {
+                        syn::Error::new(literal.span(), message.to_string())
+                    };
+                    let mut text = String::new();
+                    for piece in pieces {
+                        match piece {
+                            Piece::Text(plain) => text.push_str(plain),
+                            Piece::Value(placeholder, spec) => {
+                                let value = placeholder.value(bindings).map_err(|e| error(&e))?;
+                                let substituted = match spec {
+                                    Some(spec) => value.paste(Some(spec)),
+                                    None => Ok(value.to_string()),
+                                };
+                                text.push_str(&substituted.map_err(|message| error(&message))?);
+                            }
+                        }
+                    }
+                    out.append(if *byte {
+                        syn::LitByteStr::new(text.as_bytes(), literal.span()).token()
+                    } else {
+                        syn::LitStr::new(&text, literal.span()).token()
+                    });
+                }
+                Node::Eval(span, expr) => expr.eval(bindings)?.to_tokens(*span, out),
+                Node::Conditional {
+                    branches,
+                    otherwise,
+                } => {
+                    let mut branch = otherwise;
+                    for (condition, body) in branches {
+                        if condition.eval_bool(bindings)? {
+                            branch = body;
+                            break;
+                        }
+                    }
+                    branch.instantiate(vars, bindings, out)?;
+                }
+                Node::Repeat {
+                    span,
+                    kind,
+                    separator,
+                    body,
+                } => {
+                    let local_var;
+                    let local_vars: Vec<&LoopVar>;
+                    let (vars, combinations) = match kind {
+                        SectionKind::All => (vars, product(vars.iter().copied(), *span)?),
+                        SectionKind::Var(i) => (vars, product([vars[*i]], *span)?),
+                        SectionKind::Local(var) => {
+                            local_var = var.loop_var(bindings)?;
+                            local_vars = vars
+                                .iter()
+                                .copied()
+                                .filter(|v| !var.names().any(|ident| v.binds(ident)))
+                                .chain([&local_var])
+                                .collect();
+                            (local_vars.as_slice(), product([&local_var], *span)?)
+                        }
+                    };
+
+                    let depth = bindings.len();
+                    for (i, combination) in combinations.into_iter().enumerate() {
+                        if let (Some(separator), true) = (separator, i > 0) {
+                            out.append(separator.clone());
+                        }
+                        bindings.extend(combination);
+                        body.instantiate(vars, bindings, out)?;
+                        bindings.truncate(depth);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Compiler {
+    /// Whether `ident` is a loop variable in `scope`.
+    fn is_var(&mut self, ident: &Ident, scope: &[ScopeVar]) -> bool {
+        match scope.iter().find(|var| var.binds(ident)) {
+            Some(var) => {
+                self.whole_body |= !var.bound;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn compile(&mut self, ts: TokenStream2, scope: &[ScopeVar]) -> syn::Result<Template> {
+        let mut iter = ts.into_iter().peekable();
+        let mut template = Template::default();
+
+        while let Some(t0) = iter.next() {
+            match &t0 {
+                TokenTree::Group(g) => {
+                    let inner = self.compile(g.stream(), scope)?;
+                    if inner.is_static() {
+                        template.push_token(t0);
+                    } else {
+                        template.push(Node::Group(g.clone(), inner));
+                    }
+                }
+                TokenTree::Ident(ident)
+                    if !is_keyword(&ident.to_string())
+                        && iter.peek().map_or(false, |t1| is_punct(t1, '~')) =>
+                {
+                    template.push(self.paste(t0, None, &mut iter, scope)?);
+                }
+                TokenTree::Punct(punct)
+                    if punct.as_char() == '~' && iter.peek().map_or(false, is_paste_segment) =>
+                {
+                    template.push(self.paste(t0, None, &mut iter, scope)?);
+                }
+                TokenTree::Punct(punct) if punct.as_char() == '\'' && is_lifetime_paste(&iter) => {
+                    let first = iter.next().unwrap();
+                    template.push(self.paste(first, Some(punct.clone()), &mut iter, scope)?);
+                }
+                TokenTree::Punct(punct) if punct.as_char() == '~' => {
+                    return Err(syn::Error::new_spanned(punct, EXPECTED_PASTE_SEGMENT));
+                }
+                TokenTree::Literal(literal) => match self.literal(literal, scope) {
+                    Some(node) => template.push(node),
+                    None => template.push_token(t0),
+                },
+                TokenTree::Ident(ident) if self.is_var(ident, scope) => {
+                    template.push(Node::Var(ident.clone()));
+                }
+                TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                    match self.hash(&mut iter, scope)? {
+                        Some(node) => template.push(node),
+                        None => template.push_token(t0),
+                    }
+                }
+                _ => template.push_token(t0),
+            }
+        }
+
+        Ok(template)
+    }
+
+    /// What follows a `#`: a conditional, an expression or a repeat section.
+    fn hash(
+        &mut self,
+        iter: &mut Peekable<impl Iterator<Item = TokenTree> + Clone>,
+        scope: &[ScopeVar],
+    ) -> syn::Result<Option<Node>> {
+        if let Some(TokenTree::Group(bracket_group)) = iter.peek() {
+            if let Some(condition) = seq_if_condition(bracket_group) {
+                iter.next();
+                return self.conditional(condition, iter, scope).map(Some);
+            }
+            if bracket_group.delimiter() == Delimiter::Bracket {
+                let span = bracket_group.span();
+                if let Some(expr) = self.expr(bracket_group.stream(), scope) {
+                    iter.next();
+                    return Ok(Some(Node::Eval(span, expr)));
+                }
+            }
+        }
+
+        // Without a `*` after it, `#(...)` is left as it is, with the loop
+        // variables in the parentheses substituted like anywhere else.
+        let mut lookahead = iter.clone();
+        let Some(TokenTree::Group(paren_group)) = lookahead
+            .next_if(|t1| matches!(t1, TokenTree::Group(g) if g.delimiter() == Parenthesis))
+        else {
+            return Ok(None);
+        };
+        let Some((kind, separator)) = section_kind(&mut lookahead, scope) else {
+            return Ok(None);
+        };
+        *iter = lookahead;
+
+        let scope: Vec<_> = match &kind {
+            SectionKind::All => scope
+                .iter()
+                .map(|var| ScopeVar {
+                    bound: true,
+                    ..var.clone()
+                })
+                .collect(),
+            SectionKind::Var(i) => {
+                let mut scope = scope.to_vec();
+                scope[*i].bound = true;
+                scope
+            }
+            SectionKind::Local(var) => {
+                for ident in var.bound_vars() {
+                    self.is_var(ident, scope);
+                }
+                scope
+                    .iter()
+                    .filter(|v| !var.names().any(|ident| v.binds(ident)))
+                    .cloned()
+                    .chain([ScopeVar {
+                        names: var.names().cloned().collect(),
+                        bound: true,
+                    }])
+                    .collect()
+            }
+        };
+        let body = self.compile(paren_group.stream(), &scope)?;
+        Ok(Some(Node::Repeat {
+            span: paren_group.span(),
+            kind,
+            separator,
+            body,
+        }))
+    }
+
+    /// Parses `tokens` if they are an integer expression over loop variables,
+    /// like the `N * 8` of `#[N * 8]`. Returns `None` for anything else, such
+    /// as the `inline` of `#[inline]`, which is then left as it is.
+    fn expr(&mut self, tokens: TokenStream2, scope: &[ScopeVar]) -> Option<Expr> {
+        let expr = syn::parse2::<Expr>(tokens).ok()?;
+        let idents = expr.vars();
+        if idents.is_empty()
+            || !idents
+                .iter()
+                .all(|ident| scope.iter().any(|v| v.binds(ident)))
+        {
+            return None;
+        }
+        for ident in idents {
+            self.is_var(ident, scope);
+        }
+        Some(expr)
+    }
+
+    /// Pastes identifiers and loop variables joined by `~` into one
+    /// identifier, as in `prefix~N~_suffix`, or `~T` to start with a loop
+    /// variable. A segment can also be an expression in parentheses, as in
+    /// `~(N + 1)`, or a loop variable or expression in braces followed by a
+    /// format, as in `~{N:02x}` or `~{T:upper}`. Starting with a raw identifier gives a
+    /// raw identifier, as in `r#type~N`, and `lifetime` is the `'` of a
+    /// pasted lifetime, as in `'a~N`.
+    fn paste(
+        &mut self,
+        first: TokenTree,
+        lifetime: Option<Punct>,
+        iter: &mut Peekable<impl Iterator<Item = TokenTree> + Clone>,
+        scope: &[ScopeVar],
+    ) -> syn::Result<Node> {
+        let mut segment = match first {
+            TokenTree::Punct(_) => iter.next().unwrap(),
+            first => first,
+        };
+        let span = lifetime
+            .as_ref()
+            .map_or_else(|| segment.span(), Punct::span);
+        let mut kind = match lifetime {
+            Some(apostrophe) => PasteKind::Lifetime(apostrophe),
+            None => PasteKind::Ident,
+        };
+
+        let mut segments = vec![];
+        loop {
+            match &segment {
+                TokenTree::Ident(ident) if self.is_var(ident, scope) => {
+                    let placeholder = Placeholder::Var(ident.clone());
+                    segments.push(Segment::Value(segment.clone(), placeholder, None));
+                }
+                TokenTree::Ident(ident) => {
+                    let text = ident.to_string();
+                    match text.strip_prefix("r#") {
+                        Some(raw) if segments.is_empty() && matches!(kind, PasteKind::Ident) => {
+                            kind = PasteKind::Raw;
+                            segments.push(Segment::Text(raw.to_owned()));
+                        }
+                        _ => segments.push(Segment::Text(text)),
+                    }
+                }
+                TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                    let (placeholder, spec) = self.formatted(group, scope)?;
+                    segments.push(Segment::Value(segment.clone(), placeholder, spec));
+                }
+                TokenTree::Group(group) => match self.expr(group.stream(), scope) {
+                    Some(expr) => {
+                        let placeholder = Placeholder::Expr(expr);
+                        segments.push(Segment::Value(segment.clone(), placeholder, None));
+                    }
+                    None => {
+                        let message = "expected an expression over loop variables";
+                        return Err(syn::Error::new_spanned(group, message));
+                    }
+                },
+                _ => unreachable!(),
+            }
+
+            let Some(tilde) = iter.next_if(|t1| is_punct(t1, '~')) else {
+                break;
+            };
+            match iter.next() {
+                Some(next) if is_paste_segment(&next) => segment = next,
+                _ => return Err(syn::Error::new_spanned(tilde, EXPECTED_PASTE_SEGMENT)),
+            }
+        }
+
+        // Only joins on nightly, otherwise the span is that of the first
+        // segment.
+        let span = span.join(segment.span()).unwrap_or(span);
+        Ok(Node::Paste {
+            span,
+            kind,
+            segments,
+        })
+    }
+
+    /// The loop variable or expression in a `{...}` paste segment, and the
+    /// format after its `:` if there is one.
+    fn formatted(
+        &mut self,
+        group: &Group,
+        scope: &[ScopeVar],
+    ) -> syn::Result<(Placeholder, Option<PasteSpec>)> {
+        let mut tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+        let spec = match tokens.iter().position(|t| is_punct(t, ':')) {
+            Some(colon) => {
+                let spec = tokens.split_off(colon).into_iter().collect();
+                match PasteSpec::parse(spec) {
+                    Some(spec) => Some(spec),
+                    None => {
+                        let message = "expected a format such as `:02`, `:x` or `:snake`";
+                        return Err(syn::Error::new_spanned(group, message));
+                    }
+                }
+            }
+            None => None,
+        };
+        match self.value(tokens.into_iter().collect(), scope) {
+            Some(placeholder) => Ok((placeholder, spec)),
+            None => {
+                let message = "expected a loop variable or an expression over loop variables";
+                Err(syn::Error::new_spanned(group, message))
+            }
+        }
+    }
+
+    /// Finds the placeholders in a string or byte string literal, as in
+    /// `#[doc = "Register {N}"]`. A placeholder is a loop variable or an
+    /// integer expression over them, with an optional format like in pasted
+    /// identifiers: `{N}`, `{N * 8}`, `{N:02x}`, `{T:snake}`. Anything else in
+    /// braces, and `{{`, is left as it is, so format strings keep working.
+    fn literal(&mut self, literal: &Literal, scope: &[ScopeVar]) -> Option<Node> {
+        let (text, byte) = match syn::Lit::new(literal.clone()) {
+            syn::Lit::Str(lit) => (lit.value(), false),
+            syn::Lit::ByteStr(lit) => (String::from_utf8(lit.value()).ok()?, true),
+            _ => return None,
+        };
+        if !text.contains('{') {
+            return None;
+        }
+
+        let mut pieces = vec![];
+        let mut plain = String::new();
+        let mut rest = text.as_str();
+        while let Some(open) = rest.find('{') {
+            plain.push_str(&rest[..open]);
+            rest = &rest[open..];
+            if rest.starts_with("{{") {
+                plain.push_str("{{");
+                rest = &rest[2..];
+                continue;
+            }
+            let Some(close) = rest.find('}') else {
+                break;
+            };
+            match self.placeholder(&rest[1..close], scope) {
+                Some((placeholder, spec)) => {
+                    pieces.push(Piece::Text(std::mem::take(&mut plain)));
+                    pieces.push(Piece::Value(placeholder, spec));
+                }
+                None => plain.push_str(&rest[..=close]),
+            }
+            rest = &rest[close + 1..];
+        }
+        if pieces.is_empty() {
+            return None;
+        }
+        plain.push_str(rest);
+        pieces.push(Piece::Text(plain));
+
+        Some(Node::Literal {
+            literal: literal.clone(),
+            byte,
+            pieces,
+        })
+    }
+
+    /// One `{...}` placeholder in a literal, or `None` if it does not mention
+    /// a loop variable.
+    fn placeholder(
+        &mut self,
+        text: &str,
+        scope: &[ScopeVar],
+    ) -> Option<(Placeholder, Option<PasteSpec>)> {
+        let (text, spec) = match text.split_once(':') {
+            Some((expr, spec)) => match PasteSpec::from_str(spec) {
+                Some(spec) => (expr, Some(spec)),
+                None => (text, None),
+            },
+            None => (text, None),
+        };
+        let placeholder = self.value(text.parse().ok()?, scope)?;
+        Some((placeholder, spec))
+    }
+
+    /// A loop variable, or an integer expression over loop variables.
+    fn value(&mut self, tokens: TokenStream2, scope: &[ScopeVar]) -> Option<Placeholder> {
+        let mut iter = tokens.clone().into_iter();
+        match (iter.next(), iter.next()) {
+            (Some(TokenTree::Ident(ident)), None) => {
+                if !self.is_var(&ident, scope) {
+                    return None;
+                }
+                Some(Placeholder::Var(ident))
+            }
+            _ => self.expr(tokens, scope).map(Placeholder::Expr),
+        }
+    }
+
+    /// Compiles `#[seq_if(condition)] { ... }`, and any `else { ... }` or
+    /// `else #[seq_if(...)] { ... }` after it.
+    fn conditional(
+        &mut self,
+        mut condition: Group,
+        iter: &mut Peekable<impl Iterator<Item = TokenTree> + Clone>,
+        scope: &[ScopeVar],
+    ) -> syn::Result<Node> {
+        let mut branches = vec![];
+        loop {
+            let expr = syn::parse2::<Expr>(condition.stream())?;
+            for ident in expr.vars() {
+                self.is_var(ident, scope);
+            }
+            let body = match iter.next() {
+                Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+                _ => {
+                    let message = "expected `{` after `#[seq_if(...)]`";
+                    return Err(syn::Error::new(condition.span(), message));
+                }
+            };
+            branches.push((expr, self.compile(body.stream(), scope)?));
+
+            let Some(else_token) =
+                iter.next_if(|t| matches!(t, TokenTree::Ident(ident) if ident == "else"))
+            else {
+                return Ok(Node::Conditional {
+                    branches,
+                    otherwise: Template::default(),
+                });
+            };
+            match (iter.next(), iter.peek()) {
+                (Some(TokenTree::Group(body)), _) if body.delimiter() == Delimiter::Brace => {
+                    return Ok(Node::Conditional {
+                        branches,
+                        otherwise: self.compile(body.stream(), scope)?,
+                    });
+                }
+                (Some(TokenTree::Punct(punct)), Some(TokenTree::Group(attr)))
+                    if punct.as_char() == '#' && seq_if_condition(attr).is_some() =>
+                {
+                    condition = seq_if_condition(attr).unwrap();
+                    iter.next();
+                }
+                _ => {
+                    let message = "expected `{` or `#[seq_if(...)]` after `else`";
+                    return Err(syn::Error::new_spanned(else_token, message));
+                }
+            }
+        }
+    }
+}
+
+const EXPECTED_PASTE_SEGMENT: &str = "expected an identifier, `(...)` or `{...}` after `~`";
+
+/// Whether `text` can be the name of an identifier.
+fn is_ident(text: &str) -> bool {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) if c == '_' || c.is_alphabetic() => {
+            text != "_" && chars.all(|c| c == '_' || c.is_alphanumeric())
+        }
+        _ => false,
+    }
+}
+
+/// Whether a `'` is followed by the name of a lifetime and a `~`.
+fn is_lifetime_paste(iter: &Peekable<impl Iterator<Item = TokenTree> + Clone>) -> bool {
+    let mut ahead = iter.clone();
+    matches!(ahead.next(), Some(TokenTree::Ident(_)))
+        && ahead.next().map_or(false, |t| is_punct(&t, '~'))
+}
+
+fn is_paste_segment(t: &TokenTree) -> bool {
+    match t {
+        TokenTree::Ident(_) => true,
+        TokenTree::Group(group) => matches!(group.delimiter(), Parenthesis | Delimiter::Brace),
+        _ => false,
+    }
+}
+
+/// The `(...)` of a `[seq_if(...)]` attribute.
+fn seq_if_condition(group: &Group) -> Option<Group> {
+    if group.delimiter() != Delimiter::Bracket {
+        return None;
+    }
+    let mut iter = group.stream().into_iter();
+    match (iter.next(), iter.next(), iter.next()) {
+        (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(condition)), None)
+            if ident == "seq_if" && condition.delimiter() == Parenthesis =>
+        {
+            Some(condition)
+        }
+        _ => None,
+    }
+}
+
+/// Recognizes what follows the parentheses of a repeat section, including an
+/// optional separator right before the `*`, and consumes it. Returns `None`
+/// and consumes nothing if the parentheses are not a repeat section.
+fn section_kind(
+    iter: &mut Peekable<impl Iterator<Item = TokenTree> + Clone>,
+    scope: &[ScopeVar],
+) -> Option<(SectionKind, Option<Punct>)> {
+    let mut lookahead = iter.clone();
+    // The header is a loop variable, a separator or both, unless it gives the
+    // section a range of its own as in `I in 0..N`. A range has no `*`, `;`
+    // or braces outside of parentheses, so the search for the `*` stops at
+    // those instead of going on through the rest of the body.
+    let mut ahead = lookahead.clone();
+    let limit = match (ahead.next(), ahead.next()) {
+        (Some(TokenTree::Ident(_)), Some(TokenTree::Ident(word))) if word == "in" => usize::MAX,
+        _ => 2,
+    };
+    let mut header = vec![];
+    loop {
+        match lookahead.next()? {
+            t if is_punct(&t, '*') => break,
+            t if is_punct(&t, ';') => return None,
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => return None,
+            _ if header.len() == limit => return None,
+            t => header.push(t),
+        }
+    }
+
+    let separator = match header.as_slice() {
+        // Only single-character separators, like in `macro_rules!`.
+        [.., TokenTree::Punct(p), TokenTree::Punct(_)] if p.spacing() == Spacing::Joint => {
+            return None
+        }
+        // Written against the `*`, so that in `#(x) = *y` the `=` is not one.
+        [.., TokenTree::Punct(sep)] if sep.spacing() == Spacing::Alone => return None,
+        [.., TokenTree::Punct(sep)] => {
+            let mut separator = Punct::new(sep.as_char(), Spacing::Alone);
+            separator.set_span(sep.span());
+            Some(separator)
+        }
+        _ => None,
+    };
+    if separator.is_some() {
+        header.pop();
+    }
+    let header: TokenStream2 = header.into_iter().collect();
+
+    let kind = if header.is_empty() {
+        SectionKind::All
+    } else if let Ok(ident) = syn::parse2::<Ident>(header.clone()) {
+        SectionKind::Var(scope.iter().position(|var| var.binds(&ident))?)
+    } else {
+        SectionKind::Local(syn::parse2(header).ok()?)
+    };
+    *iter = lookahead;
+    Some((kind, separator))
+}
@@ -0,0 +1,47 @@
+// The bounds of the range may be negative, may use any integer type up to
+// i128 and u128, and may carry a suffix. The whole range of both works, from
+// i128::MIN to u128::MAX. The suffix is kept on every generated literal, so
+// `0u8..4u8` produces `0u8`, `1u8`, `2u8` and `3u8`. It is enough to write the
+// suffix on one of the bounds.
+//
+// Negative values are emitted as `-` followed by the literal, grouped so that
+// they behave as a single operand in expressions like `N * 2`. Like any other
+// negative literal, a method call such as `N.abs()` binds to the literal
+// before the sign is applied, so write `(N).abs()` instead.
+
+use seq::seq;
+
+const OFFSETS: [i32; 8] = seq!(N in -4..4 { [#(N * 2,)*] });
+
+fn offset_name(offset: i8) -> &'static str {
+    seq!(N in -2..=2i8 {
+        match offset {
+            #(N => stringify!(N),)*
+            _ => "far",
+        }
+    })
+}
+
+fn main() {
+    assert_eq!(OFFSETS, [-8, -6, -4, -2, 0, 2, 4, 6]);
+
+    assert_eq!(offset_name(-2), "- 2i8");
+    assert_eq!(offset_name(1), "1i8");
+    assert_eq!(offset_name(3), "far");
+
+    let bytes = seq!(N in 0u8..4 { [#(N,)*] });
+    assert_eq!(bytes, [0u8, 1, 2, 3]);
+
+    let wide = seq!(N in 340282366920938463463374607431768211453u128..=340282366920938463463374607431768211455 {
+        [#(N,)*]
+    });
+    assert_eq!(wide, [u128::MAX - 2, u128::MAX - 1, u128::MAX]);
+
+    let low = seq!(N in -170141183460469231731687303715884105728i128..=-170141183460469231731687303715884105727 {
+        [#(N,)*]
+    });
+    assert_eq!(low, [i128::MIN, i128::MIN + 1]);
+
+    let abs: [i64; 2] = seq!(N in -2i64..0 { [#((N).abs(),)*] });
+    assert_eq!(abs, [2, 1]);
+}
@@ -0,0 +1,36 @@
+// The range can be stepped and reversed, either with keywords after the range,
+// `0..64 step 8 rev`, or with iterator-style methods on a parenthesized range,
+// `(0..64).step_by(8).rev()`. Both forms apply the adapters in the order they
+// are written, just like the iterator methods they are named after.
+
+use seq::seq;
+
+pub struct Word(u64);
+
+impl Word {
+    seq!(N in 0..64 step 8 {
+        pub fn byte~N(&self) -> u8 {
+            (self.0 >> N) as u8
+        }
+    });
+}
+
+fn main() {
+    let word = Word(0x0807_0605_0403_0201);
+    assert_eq!(word.byte0(), 1);
+    assert_eq!(word.byte8(), 2);
+    assert_eq!(word.byte56(), 8);
+
+    let countdown = seq!(N in 0..4 rev { [#(N,)*] });
+    assert_eq!(countdown, [3, 2, 1, 0]);
+
+    let stepped = seq!(N in (0..=10).step_by(4) { [#(N,)*] });
+    assert_eq!(stepped, [0, 4, 8]);
+
+    // Stepping before reversing keeps the values that stepping picked;
+    // reversing first steps down from the end of the range instead.
+    let step_rev = seq!(N in (0..10).step_by(4).rev() { [#(N,)*] });
+    assert_eq!(step_rev, [8, 4, 0]);
+    let rev_step = seq!(N in 0..10 rev step 4 { [#(N,)*] });
+    assert_eq!(rev_step, [9, 5, 1]);
+}
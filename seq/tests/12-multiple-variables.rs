@@ -0,0 +1,37 @@
+// The header can declare several loop variables separated by commas. Without
+// repeat sections the body is expanded once for every combination of their
+// values, with the first variable changing slowest.
+//
+// A repeat section `#(...)*` likewise repeats over every combination, while
+// `#(...)R*` repeats over the named variable only. Sections nest, so an inner
+// section can see the variable bound by the outer one.
+//
+// Identifiers can be pasted from several parts, each joined with `~`, like
+// `cell_~R~_~C`.
+
+use seq::seq;
+
+pub struct Matrix([[u8; 3]; 2]);
+
+impl Matrix {
+    seq!(R in 0..2, C in 0..3 {
+        pub fn cell_~R~_~C(&self) -> u8 {
+            self.0[R][C]
+        }
+    });
+}
+
+seq!(R in 0..2, C in 0..2 {
+    const PAIRS: [(u8, u8); 4] = [#((R, C),)*];
+    const ROWS: [[u8; 2]; 2] = [#([#(R * 10 + C,)C*],)R*];
+});
+
+fn main() {
+    let m = Matrix([[1, 2, 3], [4, 5, 6]]);
+    assert_eq!(m.cell_0_0(), 1);
+    assert_eq!(m.cell_0_2(), 3);
+    assert_eq!(m.cell_1_1(), 5);
+
+    assert_eq!(PAIRS, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+    assert_eq!(ROWS, [[0, 1], [10, 11]]);
+}
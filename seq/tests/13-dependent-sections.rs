@@ -0,0 +1,36 @@
+// A repeat section can declare a loop variable of its own, written between
+// the parentheses and the `*`: `#(...)I in 0..N*`. Its range may use the
+// variables of the header or of enclosing sections as bounds, which is what
+// implementing a trait for every tuple arity needs.
+//
+// The body below expands to impls for (T0,), (T0, T1), ... (T0, ..., T5).
+
+use seq::seq;
+
+pub trait Arity {
+    const ARITY: usize;
+    fn first_sizes() -> Vec<usize>;
+}
+
+seq!(N in 1..=6 {
+    impl<#(T~I,)I in 0..N*> Arity for (#(T~I,)I in 0..N*) {
+        const ARITY: usize = N;
+
+        fn first_sizes() -> Vec<usize> {
+            vec![#(std::mem::size_of::<T~I>(),)I in 0..N*]
+        }
+    }
+});
+
+// Sections with their own range can also be nested inside other sections.
+const TRIANGLE: [usize; 6] = seq!(N in 0..3 {
+    [#(#(N * 10 + I,)I in 0..=N*)*]
+});
+
+fn main() {
+    assert_eq!(<(u8,)>::ARITY, 1);
+    assert_eq!(<(u8, u16, u32, u64, u8, u8)>::ARITY, 6);
+    assert_eq!(<(u8, u16, u32)>::first_sizes(), [1, 2, 4]);
+
+    assert_eq!(TRIANGLE, [0, 10, 11, 20, 21, 22]);
+}
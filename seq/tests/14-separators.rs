@@ -0,0 +1,44 @@
+// A single punctuation character between the closing parenthesis of a repeat
+// section and its `*`, written right before the `*`, is used as a separator,
+// like in `macro_rules!`: it is emitted between repetitions but not after the
+// last one. This also works for sections with a loop variable, as in
+// `#(...)I in 0..N,*`.
+//
+// Between the parentheses and the `*` there can only be a loop variable, a
+// range like `I in 0..N`, and a separator. Other tokens mean that `#(...)` is
+// not a repeat section, even if a `*` comes later, so it is kept as it is.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    fn sum(#(x~N: u32),*) -> u32 {
+        0 #(+ x~N)*
+    }
+
+    fn is_small(n: u32) -> bool {
+        matches!(n, #(N)|*)
+    }
+});
+
+seq!(N in 2..=3 {
+    fn tuple~N<#(T~I: Default)I in 0..N,*>() -> (#(T~I)I in 0..N,*) {
+        (#(T~I::default())I in 0..N,*)
+    }
+});
+
+seq!(N in 0..2 {
+    const LITERAL~N: &str = stringify!(#(N) = y * z);
+    const DEREF~N: &str = stringify!(#(N) = *z);
+});
+
+fn main() {
+    assert_eq!(sum(1, 2, 3, 4), 10);
+    assert!(is_small(3));
+    assert!(!is_small(4));
+
+    assert_eq!(tuple2::<u8, bool>(), (0, false));
+    assert_eq!(tuple3::<u8, bool, char>(), (0, false, '\0'));
+
+    assert_eq!(LITERAL1, "# (1) = y * z");
+    assert_eq!(DEREF1, "# (1) = * z");
+}
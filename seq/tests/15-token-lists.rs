@@ -0,0 +1,55 @@
+// Instead of a range, a loop variable can iterate over a bracketed list of
+// items, each of which is any sequence of tokens: `T in [u8, u16, u32]`.
+// Commas inside angle brackets do not separate items, so `HashMap<K, V>` is a
+// single item.
+//
+// The form `(I, T) in [...]` additionally binds I to the position of each
+// item, starting at 0. Both names can select the variable of a repeat section,
+// and items that are a single identifier can be pasted with `~`.
+
+use seq::seq;
+use std::collections::HashMap;
+
+pub trait Width {
+    const WIDTH: u32;
+}
+
+seq!(T in [u8, u16, u32, u64] {
+    impl Width for T {
+        const WIDTH: u32 = T::BITS;
+    }
+});
+
+seq!((I, T) in [u8, i16] {
+    #[allow(non_camel_case_types)]
+    pub enum Kind {
+        #(T,)*
+    }
+
+    pub fn position_of(kind: Kind) -> usize {
+        match kind {
+            #(Kind::T => I,)*
+        }
+    }
+});
+
+seq!((I, T) in [u8, Vec<u8>, HashMap<String, u8>] {
+    pub fn default_~I() -> T {
+        <T>::default()
+    }
+});
+
+const NAMES: [&str; 3] = seq!(T in [u8, Vec<u8>, HashMap<String, u8>] {
+    [#(stringify!(T),)*]
+});
+
+fn main() {
+    assert_eq!(<u16 as Width>::WIDTH, 16);
+    assert_eq!(<u64 as Width>::WIDTH, 64);
+
+    assert_eq!(position_of(Kind::i16), 1);
+    assert_eq!(default_0(), 0);
+    assert_eq!(default_1(), Vec::<u8>::new());
+    assert!(default_2().is_empty());
+    assert_eq!(NAMES, ["u8", "Vec < u8 >", "HashMap < String, u8 >"]);
+}
@@ -0,0 +1,102 @@
+// A loop variable pasted into an identifier can be given a format by writing
+// it in braces, like the placeholders of a format string:
+//
+//     `:02`  zero-padded to two digits       Reg~{N:02}    Reg07
+//     `:x`   lower hex, `:X` upper hex       Addr~{N:x}    Addra
+//     `:02X` both                            REG~{N:02X}   REG1F
+//     `:b`   binary, `:o` octal
+//
+// Items of a token list can be converted between cases with `:lower`,
+// `:upper`, `:snake`, `:upper_snake`, `:camel` and `:lower_camel`.
+//
+// The braces keep formats apart from the `:` of a field or a type that can
+// follow a pasted identifier, so `P { f~N: 0 }` is a struct literal with
+// fields `f0`, `f1`... as usual.
+//
+// Pasting can start with a loop variable, as in `~{T:snake}`, and can put the
+// variable in the middle of an identifier, as in `get_~N~_raw`. Keywords are
+// never pasted onto, so `fn ~{T:snake}()` declares a function.
+
+use seq::seq;
+
+seq!(N in 0..32 {
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    pub enum Register {
+        #(REG~{N:02X},)*
+    }
+});
+
+pub struct Regs([u8; 4]);
+
+impl Regs {
+    seq!(N in 0..4 {
+        pub fn get_~N~_raw(&self) -> u8 {
+            self.0[N]
+        }
+    });
+}
+
+seq!(N in 8..=10 {
+    const ADDR~{N:X}: u32 = N;
+    const BITS~{N:04b}: u32 = N;
+
+    fn addr_~{N:x}() -> u32 {
+        N
+    }
+});
+
+seq!(T in [BigEndian, LittleEndian, HTTPServer] {
+    #(
+        pub fn ~{T:snake}() -> &'static str {
+            stringify!(T)
+        }
+
+        pub const ~{T:upper_snake}: u8 = 0;
+    )*
+});
+
+pub struct Fields {
+    pub f0: u8,
+    pub f1: u8,
+}
+
+pub struct Names {
+    pub f0: &'static str,
+}
+
+seq!(N in 0..2 {
+    pub fn zeroed() -> Fields {
+        Fields { #(f~N: 0,)* }
+    }
+
+    pub fn sum(fields: &Fields) -> u8 {
+        let Fields { #(f~N: x~N,)* } = fields;
+        #(x~N +)* 0
+    }
+});
+
+seq!(N in 0..1 {
+    pub fn names() -> Names {
+        Names { #(f~N: "text",)* }
+    }
+});
+
+fn main() {
+    assert_eq!(format!("{:?}", Register::REG00), "REG00");
+    assert_eq!(format!("{:?}", Register::REG1F), "REG1F");
+
+    assert_eq!(Regs([1, 2, 3, 4]).get_2_raw(), 3);
+
+    assert_eq!(ADDR9 + ADDRA, 19);
+    assert_eq!(addr_a(), 10);
+    assert_eq!(BITS1000, 8);
+
+    assert_eq!(big_endian(), "BigEndian");
+    assert_eq!(http_server(), "HTTPServer");
+    assert_eq!(LITTLE_ENDIAN, 0);
+
+    assert_eq!(sum(&zeroed()), 0);
+    assert_eq!(sum(&Fields { f0: 1, f1: 2 }), 3);
+    assert_eq!(names().f0, "text");
+}
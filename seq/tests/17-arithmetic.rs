@@ -0,0 +1,56 @@
+// Integer expressions over loop variables are evaluated while expanding, so
+// they can be used where Rust requires a literal or an identifier:
+//
+//     `#[N * 8]`     becomes the literal `N * 8`, e.g. `24` for N = 3
+//     `field_~(N+1)` pastes the value, e.g. `field_4`
+//
+// The operators are `+ - * / % << >> & ^ |`, with Rust's precedence, and
+// parentheses. Range bounds can be expressions too, as in `0..(N + 1)`.
+//
+// A bracketed expression that does not use a loop variable, like the
+// `inline` of `#[inline]`, is an ordinary attribute and is left alone.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    #[repr(align(#[1 << N]))]
+    pub struct Aligned~N(u8);
+
+    pub struct Bits~N {
+        pub field_~(N + 1): [u8; #[N * 8 / 8 + 1]],
+    }
+});
+
+macro_rules! width {
+    ($name:ident = $width:literal) => {
+        pub const $name: u32 = $width;
+    };
+}
+
+seq!(N in 1..=4 {
+    width!(WIDTH_~N = #[N * 8]);
+});
+
+seq!(N in 0..3 {
+    #[inline]
+    fn triangle~N() -> u32 {
+        0 #(+ I)I in 0..(N + 1)*
+    }
+});
+
+fn main() {
+    assert_eq!(std::mem::align_of::<Aligned0>(), 1);
+    assert_eq!(std::mem::align_of::<Aligned3>(), 8);
+
+    let bits = Bits2 { field_3: [0; 3] };
+    assert_eq!(bits.field_3.len(), 3);
+
+    assert_eq!(WIDTH_1, 8);
+    assert_eq!(WIDTH_4, 32);
+
+    assert_eq!(triangle0(), 0);
+    assert_eq!(triangle2(), 3);
+
+    let masks = seq!(N in 0..4 { [#(#[0xF << (N * 4)],)*] });
+    assert_eq!(masks, [0xF, 0xF0, 0xF00, 0xF000]);
+}
@@ -0,0 +1,46 @@
+// Loop variables are also substituted inside string and byte string literals,
+// including doc comments, which are `#[doc = "..."]` attributes. They are
+// written in braces, with the same formats as in pasted identifiers and with
+// the same integer expressions as in `#[N * 8]`:
+//
+//     "Register {N}"        "Register 3"
+//     "bits {N * 8}"        "bits 24"
+//     b"REG{N:02}"          b"REG03"
+//     "{T:snake}"           "u_int" for T = UInt
+//
+// Braces around anything that is not a loop variable, such as the `{}` and
+// `{0}` of format strings, are left as they are, and so is `{{`.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    /// Register {N}, {N * 8} bits from the start.
+    pub struct Reg~N;
+
+    impl Reg~N {
+        pub const NAME: &'static str = "Reg{N}";
+        pub const TAG: &'static [u8] = b"REG{N:02}";
+
+        pub fn describe(value: u8) -> String {
+            format!("Reg{N}[{}] = {:#x} {{N}}", N, value)
+        }
+    }
+});
+
+pub struct UInt;
+pub struct Float;
+
+seq!(T in [UInt, Float] {
+    impl T {
+        pub const NAME: &'static str = "{T:snake}";
+    }
+});
+
+fn main() {
+    assert_eq!(Reg0::NAME, "Reg0");
+    assert_eq!(Reg3::NAME, "Reg3");
+    assert_eq!(Reg2::TAG, b"REG02");
+    assert_eq!(Reg1::describe(255), "Reg1[1] = 0xff {N}");
+    assert_eq!(UInt::NAME, "u_int");
+    assert_eq!(Float::NAME, "float");
+}
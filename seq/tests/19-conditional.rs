@@ -0,0 +1,60 @@
+// `#[seq_if(condition)] { ... }` keeps the tokens in the braces only in the
+// iterations where the condition holds, and `else { ... }` gives the tokens
+// for the others. Conditions compare integer expressions over the loop
+// variables with `== != < <= > >=`, and are combined with `&& || !`:
+//
+//     #[seq_if(N == 1)] { ... }
+//     else #[seq_if(N % 2 == 0 && N <= 16)] { ... }
+//     else { ... }
+//
+// The braces only group the tokens of a branch and do not appear in the
+// output, so a branch can be part of an item, an expression or a list.
+
+use seq::seq;
+
+pub trait Width {
+    type Type;
+    const POWER_OF_TWO: bool;
+}
+
+seq!(N in 1..=16 {
+    pub struct U~N;
+
+    impl Width for U~N {
+        #[seq_if(N <= 8)] {
+            type Type = u8;
+        } else {
+            type Type = u16;
+        }
+
+        const POWER_OF_TWO: bool = #[seq_if(N & (N - 1) == 0)] { true } else { false };
+    }
+});
+
+seq!(N in 1..=6 {
+    fn kind~N() -> &'static str {
+        #[seq_if(N == 1)] {
+            "one"
+        } else #[seq_if(N % 2 == 0 && !(N > 4))] {
+            "small even"
+        } else {
+            "other"
+        }
+    }
+});
+
+fn main() {
+    let evens = seq!(N in 0..10 { [#(#[seq_if(N % 2 == 0)] { N, })*] });
+    assert_eq!(evens, [0, 2, 4, 6, 8]);
+
+    assert_eq!(std::mem::size_of::<<U8 as Width>::Type>(), 1);
+    assert_eq!(std::mem::size_of::<<U9 as Width>::Type>(), 2);
+    assert!(U1::POWER_OF_TWO);
+    assert!(U8::POWER_OF_TWO);
+    assert!(!U12::POWER_OF_TWO);
+
+    assert_eq!(kind1(), "one");
+    assert_eq!(kind4(), "small even");
+    assert_eq!(kind5(), "other");
+    assert_eq!(kind6(), "other");
+}
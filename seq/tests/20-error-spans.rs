@@ -0,0 +1,42 @@
+// Mistakes in a seq! body are reported as one error pointing at the tokens at
+// fault, and nothing else is expanded for that invocation.
+//
+// A loop, and every repeat section, is limited to 65536 iterations. Ranges
+// longer than that are almost always a typo, and are reported before the
+// compiler runs out of memory expanding them.
+//
+// Without a `*` after it, `#(...)` is not a repeat section and is kept as it
+// is, with the loop variables in it substituted.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    const X~N~: u8 = 0;
+});
+
+seq!(N in 0..4 {
+    const ~N: u8 = 0;
+});
+
+seq!(N in 0..4 {
+    const X~(N - 2): u8 = 0;
+});
+
+seq!(N in 0..100000 {
+    const _: u8 = N;
+});
+
+seq!(I in 0..300, J in 0..300 {
+    #(const _: (u8, u8) = (I, J);)*
+});
+
+seq!(N in 0..4 {
+    #[seq_if(N > 1)]
+    const X~N: u8 = 0;
+});
+
+seq!(N in 3..4 {
+    compile_error!(stringify!(#(N + 1)));
+});
+
+fn main() {}
@@ -0,0 +1,78 @@
+// `#[seq_for]` is the attribute form of seq!, for items that should stay
+// ordinary Rust that rustfmt and IDEs understand. Only the variants, fields
+// or associated items marked `#[seq_each]` are repeated:
+//
+//     #[seq_for(N in 0..4)]
+//     pub enum Register {
+//         Status,
+//         #[seq_each]
+//         #[seq_name(Reg~N)]
+//         Reg = 0x10 + N,
+//     }
+//
+// A function-like macro and an attribute macro cannot share a name, so the
+// attribute is not called `seq`. The item is also parsed as Rust before the
+// attribute sees it, which rules out `#(...)*` and `~` in names. Instead
+// `#[seq_each]` works like a repeat section around the marked item, taking
+// the same headers (`#[seq_each(N)]`, `#[seq_each(I in 0..2)]`), and
+// `#[seq_name(...)]` gives the pasted name of each copy. Loop variables are
+// substituted everywhere in the marked item, including in doc comments and in
+// the tokens of macro invocations, where `~` can be used freely.
+
+use seq::seq_for;
+
+#[seq_for(N in 0..4)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(u8)]
+pub enum Register {
+    Status = 0,
+    #[seq_each]
+    #[seq_name(Reg~N)]
+    Reg = 0x10 + N,
+    Control = 0x20,
+}
+
+#[seq_for(N in 0..4)]
+#[derive(Default)]
+pub struct Registers {
+    pub status: u8,
+    /// Data register {N}.
+    #[seq_each]
+    #[seq_name(data~N)]
+    pub data: u32,
+}
+
+#[seq_for(N in 0..2)]
+pub struct Pair(#[seq_each] u16, u8);
+
+#[seq_for(N in 0..4)]
+impl Register {
+    #[seq_each]
+    #[seq_name(is_reg~N)]
+    pub fn is_reg(self) -> bool {
+        matches!(self, Register::Reg~N)
+    }
+
+    pub fn is_data(self) -> bool {
+        !matches!(self, Register::Status | Register::Control)
+    }
+}
+
+fn main() {
+    assert_eq!(Register::Reg0 as u8, 0x10);
+    assert_eq!(Register::Reg3 as u8, 0x13);
+    assert_eq!(Register::Control as u8, 0x20);
+    assert!(Register::Reg2.is_reg2());
+    assert!(!Register::Reg2.is_reg1());
+    assert!(Register::Reg1.is_data());
+
+    let registers = Registers {
+        data3: 7,
+        ..Default::default()
+    };
+    assert_eq!(registers.status + registers.data0 as u8, 0);
+    assert_eq!(registers.data1 + registers.data2 + registers.data3, 7);
+
+    let pair = Pair(1, 2, 3);
+    assert_eq!(pair.0 + pair.1 + pair.2 as u16, 6);
+}
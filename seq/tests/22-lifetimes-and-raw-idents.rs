@@ -0,0 +1,60 @@
+// Pasting onto a lifetime gives a lifetime, so generic parameters can be
+// generated like any other list:
+//
+//     impl<#('a~N,)*> Trait for (#(&'a~N T,)*) { ... }
+//
+// Pasting onto a raw identifier gives a raw identifier, as in `r#type~N` for
+// `r#type0`, `r#type1`... which is the same identifier as `type0`. A paste
+// that would give a keyword without `r#` in front, such as `~{T:lower}` with
+// `T` bound to `Type`, is reported as an error rather than producing a
+// keyword where a name was expected.
+
+use seq::seq;
+
+pub trait First {
+    type Output;
+
+    fn first(self) -> Self::Output;
+}
+
+seq!(N in 0..4 {
+    impl<#('a~N,)*> First for (#(&'a~N u8,)*) {
+        type Output = &'a0 u8;
+
+        fn first(self) -> &'a0 u8 {
+            self.0
+        }
+    }
+
+    pub fn longest<'a, #('b~N: 'a,)*>(#(s~N: &'b~N str,)*) -> &'a str {
+        let mut longest = s0;
+        #(
+            if s~N.len() > longest.len() {
+                longest = s~N;
+            }
+        )*
+        longest
+    }
+});
+
+seq!(N in 0..3 {
+    #[derive(Default)]
+    pub struct Tokens {
+        #(pub r#type~N: u8,)*
+        pub r#match: u8,
+    }
+});
+
+fn main() {
+    let (a, b, c, d) = (1, 2, 3, 4);
+    assert_eq!(*(&a, &b, &c, &d).first(), 1);
+    assert_eq!(longest("a", "abc", "ab", ""), "abc");
+
+    let tokens = Tokens {
+        type2: 2,
+        r#type1: 1,
+        r#match: 3,
+        ..Default::default()
+    };
+    assert_eq!(tokens.type0 + tokens.type1 + tokens.r#type2 + tokens.r#match, 6);
+}
@@ -0,0 +1,34 @@
+// Values and expressions over them are not limited to one integer type, so a
+// table can span the top half of u128, and arithmetic in `#[...]` can go past
+// i128::MAX on the way to a result. Negative values still divide, shift and
+// combine bits like the signed types do.
+
+use seq::seq;
+
+const QUARTER: u128 = 1 << 126;
+
+seq!(N in 0u128..3 {
+    const TOP~N: u128 = #[340282366920938463463374607431768211455 - N];
+    const HIGH~N: u128 = #[(N + 1) << 126];
+});
+
+fn main() {
+    assert_eq!([TOP0, TOP1, TOP2], [u128::MAX, u128::MAX - 1, u128::MAX - 2]);
+    assert_eq!([HIGH0, HIGH1, HIGH2], [QUARTER, QUARTER * 2, QUARTER * 3]);
+
+    let steps = seq!(N in (0..=340282366920938463463374607431768211455u128).step_by(85070591730234615865843651857942052864).rev() {
+        [#(N,)*]
+    });
+    assert_eq!(steps, [QUARTER * 3, QUARTER * 2, QUARTER, 0]);
+
+    let thirds = seq!(N in 0..=340282366920938463463374607431768211455u128 step 113427455640312821154458202477256070485 {
+        [#(N,)*]
+    });
+    assert_eq!(thirds, [0, u128::MAX / 3, u128::MAX / 3 * 2, u128::MAX]);
+
+    let signed: [(i32, i32, i32, i32, i32, i32, i32); 7] = seq!(N in -3..=3 {
+        [#((#[N / 2], #[N % 2], #[N >> 1], #[N << 2], #[N & -2], #[N ^ 1], #[N | 2]),)*]
+    });
+    let expected = [-3, -2, -1, 0, 1, 2, 3].map(|n: i32| (n / 2, n % 2, n >> 1, n << 2, n & -2, n ^ 1, n | 2));
+    assert_eq!(signed, expected);
+}
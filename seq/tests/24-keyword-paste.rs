@@ -0,0 +1,11 @@
+// A paste that gives a keyword is an error, since the keyword would not be
+// read as a name. A raw identifier in front, as in `r#type~N`, pastes a name
+// that is allowed to be a keyword.
+
+use seq::seq;
+
+seq!(T in [Type, Move] {
+    pub struct T { pub ~{T:lower}: u8 }
+});
+
+fn main() {}
@@ -0,0 +1,19 @@
+// The result of an expression takes the suffix of its operands, so it has to
+// fit in the type that suffix names. A value that does not is reported at the
+// expression, rather than emitted as a literal the compiler rejects.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    const _: u8 = #[N * 100u8];
+});
+
+seq!(N in 0u8..2 {
+    const _: u8 = #[N - 5];
+});
+
+seq!(N in 0i8..2 {
+    const _: i8 = #[-(N + 127)];
+});
+
+fn main() {}
@@ -10,4 +10,20 @@ fn tests() {
     t.pass("tests/07-inclusive-range.rs");
     t.compile_fail("tests/08-ident-span.rs");
     t.pass("tests/09-interaction-with-macrorules.rs");
+    t.pass("tests/10-signed-ranges.rs");
+    t.pass("tests/11-step-and-rev.rs");
+    t.pass("tests/12-multiple-variables.rs");
+    t.pass("tests/13-dependent-sections.rs");
+    t.pass("tests/14-separators.rs");
+    t.pass("tests/15-token-lists.rs");
+    t.pass("tests/16-paste-formats.rs");
+    t.pass("tests/17-arithmetic.rs");
+    t.pass("tests/18-string-literals.rs");
+    t.pass("tests/19-conditional.rs");
+    t.compile_fail("tests/20-error-spans.rs");
+    t.pass("tests/21-attribute.rs");
+    t.pass("tests/22-lifetimes-and-raw-idents.rs");
+    t.pass("tests/23-u128-range.rs");
+    t.compile_fail("tests/24-keyword-paste.rs");
+    t.compile_fail("tests/25-suffix-overflow.rs");
 }